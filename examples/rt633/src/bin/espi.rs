@@ -7,8 +7,8 @@ use defmt::{error, info};
 use embassy_executor::Spawner;
 use embassy_imxrt::bind_interrupts;
 use embassy_imxrt::espi::{
-    Base, BaseOrAsz, BootStatus, Capabilities, Config, Direction, Espi, Event, InterruptHandler, Len, Maxspd,
-    PortConfig,
+    Base, BaseOrAsz, BootStatus, Capabilities, Config, Direction, Espi, Event, FlashAccess, InterruptHandler, Len,
+    Maxspd, PortConfig, VirtualWire,
 };
 use embassy_imxrt::peripherals::ESPI;
 use {defmt_rtt as _, panic_probe as _, rt633_examples as _};
@@ -67,7 +67,8 @@ async fn main(_spawner: Spawner) {
             ],
             ..Default::default()
         },
-    );
+    )
+    .unwrap();
 
     info!("Hello eSPI");
 
@@ -94,6 +95,17 @@ async fn main(_spawner: Spawner) {
                     "eSPI PeripheralEvent Port: {}, direction: {}, address: {}, offset: {}, length: {}",
                     port_event.port, port_event.direction, port_event.offset, port_event.base_addr, port_event.length,
                 );
+
+                // SAFETY: Port windows are carved out of the espi_data region
+                if let Ok(buf) = unsafe { espi.port_buffer(port_event.port) } {
+                    let end = (port_event.offset + port_event.length).min(buf.len());
+                    info!(
+                        "eSPI Port {} data: {:02x}",
+                        port_event.port,
+                        &buf[port_event.offset.min(end)..end]
+                    );
+                }
+
                 espi.complete_port(port_event.port).await;
             }
             Ok(Event::OOBEvent(port_event)) => {
@@ -101,11 +113,49 @@ async fn main(_spawner: Spawner) {
                     "eSPI OOBEvent Port: {}, direction: {}, address: {}, offset: {}, length: {}",
                     port_event.port, port_event.direction, port_event.offset, port_event.base_addr, port_event.length,
                 );
+
+                // Direction false is a message from the host, e.g. a tunneled SMBus packet
+                if !port_event.direction {
+                    let mut message = [0_u8; 73];
+                    // SAFETY: Port windows are carved out of the espi_data region
+                    match unsafe { espi.oob_read_data(&port_event, &mut message) } {
+                        Ok(len) => info!("eSPI OOB message: {:02x}", &message[..len]),
+                        Err(_) => error!("Failed to read OOB message"),
+                    }
+                }
+
                 espi.complete_port(port_event.port).await;
             }
+            Ok(Event::FlashEvent(flash_event)) => {
+                info!(
+                    "eSPI FlashEvent Port: {}, access: {}, flash address: {:x}, length: {}",
+                    flash_event.port, flash_event.access, flash_event.flash_addr, flash_event.length,
+                );
+
+                // No flash attached in this example, reads return erased data
+                let length = match flash_event.access {
+                    FlashAccess::Read => {
+                        // SAFETY: Port windows are carved out of the espi_data region
+                        if let Ok(buf) = unsafe { espi.port_buffer_mut(flash_event.port) } {
+                            let end = flash_event.length.min(buf.len());
+                            buf[..end].fill(0xff);
+                        }
+                        flash_event.length
+                    }
+                    _ => 0,
+                };
+
+                if espi.complete_flash(flash_event.port, length).await.is_err() {
+                    error!("Failed to complete flash request");
+                }
+            }
             Ok(Event::WireChange(event)) => {
                 info!("Wire Change! {}", event);
 
+                if espi.get_virtual_wire(VirtualWire::SlpS3) {
+                    info!("Host entering S3");
+                }
+
                 if event.is_host_reset_warn() {
                     espi.host_reset_ack();
                 }
@@ -0,0 +1,238 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use embassy_executor::Spawner;
+use embassy_imxrt::flexspi::nor::{
+    FlexSpiFlashPort, FlexSpiFlashPortDeviceInstance, FlexspiConfig, FlexspiConfigPortData, FlexspiDeviceConfig,
+    FlexspiNorStorageBus, InterruptHandler,
+};
+use embassy_imxrt::pac::flexspi::flshcr1::Csintervalunit;
+use embassy_imxrt::pac::flexspi::flshcr2::Awrwaitunit;
+use embassy_imxrt::pac::flexspi::flshcr4::{Wmena, Wmenb};
+use embassy_imxrt::{bind_interrupts, clocks, peripherals};
+use embassy_time::{Duration, Instant, Timer};
+use storage_bus::nor::{
+    BlockingNorStorageBusDriver, NorStorageBusWidth, NorStorageCmd, NorStorageCmdMode, NorStorageCmdType,
+    NorStorageDummyCycles,
+};
+use {defmt_rtt as _, panic_probe as _};
+
+// Last sector of the first 16MB, reachable with 3 byte addressing
+const ADDR: u32 = 0xFF_F000;
+
+// Standard single lane SPI NOR opcodes
+const CMD_READ: u8 = 0x03;
+const CMD_PAGE_PROGRAM: u8 = 0x02;
+const CMD_SECTOR_ERASE: u8 = 0x20;
+const CMD_READ_STATUS: u8 = 0x05;
+const CMD_WRITE_ENABLE: u8 = 0x06;
+
+const STATUS_WIP: u8 = 0x01;
+
+// Read through both the DMA and the polled FIFO path and compared
+const COMPARE_LEN: usize = 64;
+
+bind_interrupts!(struct Irqs {
+    FLEXSPI => InterruptHandler<peripherals::FLEXSPI>;
+});
+
+// Single lane SDR command with an optional 3 byte address and data phase
+fn spi_cmd(opcode: u8, addr: Option<u32>, cmdtype: Option<NorStorageCmdType>, len: usize) -> NorStorageCmd {
+    NorStorageCmd {
+        cmd_lb: opcode,
+        cmd_ub: None,
+        addr,
+        addr_width: addr.map(|_| 24),
+        bus_width: NorStorageBusWidth::Single,
+        mode: NorStorageCmdMode::SDR,
+        dummy: NorStorageDummyCycles::Clocks(0),
+        data_bytes: cmdtype.as_ref().map(|_| len as u32),
+        cmdtype,
+    }
+}
+
+/// Keeps running while the flash is busy, showing the erase does not stall the executor
+#[embassy_executor::task]
+async fn heartbeat() {
+    loop {
+        info!("heartbeat");
+        Timer::after_millis(50).await;
+    }
+}
+
+#[embassy_executor::main]
+async fn main(spawner: Spawner) {
+    let mut p = embassy_imxrt::init(Default::default());
+
+    let flash_config = FlexspiDeviceConfig {
+        // Root clock as configured by clock init (FFRO)
+        flexspi_root_clk: clocks::flexspi_clock_rate().unwrap(),
+        is_sck2_enabled: false,
+        // Flash size in this struct is in KB, so divide by 1KB
+        flash_size_kb: 0x4000, // 16 MB
+        cs_interval_unit: Csintervalunit::Csintervalunit0,
+        cs_interval: 0,
+        cs_hold_time: 3,
+        cs_setup_time: 3,
+        data_valid_time: 2,
+        columnspace: 0,
+        enable_word_address: false,
+        awr_seq_index: 0,
+        awr_seq_number: 0,
+        ard_seq_index: 0,
+        ard_seq_number: 0,
+        ahb_write_wait_unit: Awrwaitunit::Awrwaitunit2,
+        ahb_write_wait_interval: 0,
+        enable_write_mask_port_a: Wmena::Wmena0,
+        enable_write_mask_port_b: Wmenb::Wmenb0,
+    };
+    let flexspi_config = FlexspiConfig::default();
+
+    let port_data = FlexspiConfigPortData {
+        port: FlexSpiFlashPort::PortB,
+        dev_instance: FlexSpiFlashPortDeviceInstance::DeviceInstance0,
+        rx_watermark: 0x8,
+        tx_watermark: 0x8,
+        read_only: false,
+    };
+
+    // Command data is moved through DMA and every command completes on the IPCMDDONE interrupt.
+    // The peripherals are reborrowed so a blocking bus can take them over afterwards.
+    let mut flexspi_storage = FlexspiNorStorageBus::new_async_single_config(
+        p.FLEXSPI.reborrow(), // FlexSPI peripheral
+        p.PIO1_11.reborrow(), // DATA0 / MOSI
+        p.PIO1_12.reborrow(), // DATA1 / MISO
+        p.PIO1_29.reborrow(), // SCLK
+        p.PIO2_19.reborrow(), // CS0
+        Irqs,
+        port_data,
+        p.DMA0_CH28, // FlexSPI RX request
+        p.DMA0_CH29, // FlexSPI TX request
+    );
+
+    let configured = flexspi_storage
+        .configport_mut()
+        .configure_flexspi(&flexspi_config)
+        .and_then(|_| {
+            flexspi_storage
+                .configport_mut()
+                .configure_device_port(&flash_config, &flexspi_config)
+        });
+    if let Err(e) = configured {
+        error!("Failed to configure FlexSPI: {}", e);
+        loop {
+            Timer::after_millis(2000).await;
+        }
+    }
+
+    spawner.spawn(heartbeat()).unwrap();
+
+    let status_cmd = spi_cmd(CMD_READ_STATUS, None, Some(NorStorageCmdType::Read), 1);
+    let poll_interval = Duration::from_millis(5);
+
+    let mut write_data = [0_u8; 32];
+    for (i, item) in write_data.iter_mut().enumerate() {
+        *item = i as u8;
+    }
+    let mut read_data = [0_u8; 32];
+
+    // The heartbeat task keeps printing while the erase runs in the flash
+    let start = Instant::now();
+    let erased = async {
+        flexspi_storage
+            .send_command(spi_cmd(CMD_WRITE_ENABLE, None, None, 0), None, None)
+            .await?;
+        flexspi_storage
+            .send_command(spi_cmd(CMD_SECTOR_ERASE, Some(ADDR), None, 0), None, None)
+            .await?;
+        flexspi_storage
+            .wait_until_ready(&status_cmd, STATUS_WIP, poll_interval)
+            .await
+    }
+    .await;
+    if erased.is_err() {
+        error!("Sector erase failed");
+        return;
+    }
+    info!("Sector erase took {} ms", start.elapsed().as_millis());
+
+    let result = async {
+        flexspi_storage
+            .send_command(spi_cmd(CMD_WRITE_ENABLE, None, None, 0), None, None)
+            .await?;
+        flexspi_storage
+            .send_command(
+                spi_cmd(
+                    CMD_PAGE_PROGRAM,
+                    Some(ADDR),
+                    Some(NorStorageCmdType::Write),
+                    write_data.len(),
+                ),
+                None,
+                Some(&write_data),
+            )
+            .await?;
+        flexspi_storage
+            .wait_until_ready(&status_cmd, STATUS_WIP, poll_interval)
+            .await?;
+        flexspi_storage
+            .send_command(
+                spi_cmd(CMD_READ, Some(ADDR), Some(NorStorageCmdType::Read), read_data.len()),
+                Some(&mut read_data),
+                None,
+            )
+            .await
+    }
+    .await;
+
+    match result {
+        Ok(()) if read_data == write_data => info!("Read back matches written data"),
+        Ok(()) => error!("Read back mismatch: {:02X}", read_data),
+        Err(_) => error!("SPI NOR access failed"),
+    }
+
+    // The same bytes read by DMA on the async bus and by polling the FIFO on a blocking bus
+    let mut dma_data = [0_u8; COMPARE_LEN];
+    let dma_read = flexspi_storage
+        .send_command(
+            spi_cmd(CMD_READ, Some(ADDR), Some(NorStorageCmdType::Read), COMPARE_LEN),
+            Some(&mut dma_data),
+            None,
+        )
+        .await;
+    drop(flexspi_storage);
+
+    let mut blocking_storage = FlexspiNorStorageBus::new_blocking_single_config(
+        p.FLEXSPI, p.PIO1_11, p.PIO1_12, p.PIO1_29, p.PIO2_19, port_data,
+    );
+    let mut polled_data = [0_u8; COMPARE_LEN];
+    let configured = blocking_storage
+        .configport_mut()
+        .configure_flexspi(&flexspi_config)
+        .and_then(|_| {
+            blocking_storage
+                .configport_mut()
+                .configure_device_port(&flash_config, &flexspi_config)
+        });
+    let polled_read = configured.is_ok()
+        && blocking_storage
+            .send_command(
+                spi_cmd(CMD_READ, Some(ADDR), Some(NorStorageCmdType::Read), COMPARE_LEN),
+                Some(&mut polled_data),
+                None,
+            )
+            .is_ok();
+
+    if dma_read.is_err() || !polled_read {
+        error!("DMA vs polled comparison failed");
+    } else if dma_data == polled_data {
+        info!("DMA and polled reads of {} bytes match", COMPARE_LEN);
+    } else {
+        error!("DMA read {:02X} but polled read {:02X}", dma_data, polled_data);
+    }
+
+    loop {
+        Timer::after_millis(2000).await;
+    }
+}
@@ -0,0 +1,124 @@
+#![no_std]
+#![no_main]
+
+use core::ops::Range;
+
+use defmt::{error, info};
+use embassy_embedded_hal::adapter::BlockingAsync;
+use embassy_executor::Spawner;
+use embassy_imxrt::flexspi::nor::{
+    FlexSpiFlashPort, FlexSpiFlashPortDeviceInstance, FlexspiConfig, FlexspiConfigPortData, FlexspiNorStorageBus,
+};
+use embassy_imxrt_examples::macronix::{self, MacronixDeviceDriver};
+use embassy_time::Timer;
+use sequential_storage::cache::NoCache;
+use sequential_storage::erase_all;
+use sequential_storage::map::{fetch_item, store_item};
+use {defmt_rtt as _, panic_probe as _};
+
+// Flash region reserved for the key-value store: 32 sectors of 4KB at the end of the 64MB part
+const STORAGE_RANGE: Range<u32> = 0x3FC_0000..0x3FE_0000;
+
+// Key under which the boot counter is stored
+const BOOT_COUNT_KEY: u8 = 0x01;
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    let flash_config = macronix::device_config();
+    let flexspi_config = FlexspiConfig::default();
+
+    let mut flexspi_storage = FlexspiNorStorageBus::new_blocking_octal_config(
+        p.FLEXSPI, // FlexSPI peripheral
+        p.PIO1_11,
+        p.PIO1_12,
+        p.PIO1_13,
+        p.PIO1_14,
+        p.PIO2_17,
+        p.PIO2_18,
+        p.PIO2_22,
+        p.PIO2_23,
+        p.PIO1_29,
+        p.PIO2_19,
+        FlexspiConfigPortData {
+            port: FlexSpiFlashPort::PortB,                                 // FlexSPI port
+            dev_instance: FlexSpiFlashPortDeviceInstance::DeviceInstance0, // FlexSPI device instance
+            rx_watermark: 0x8,
+            tx_watermark: 0x8,
+            read_only: false,
+        },
+    );
+
+    flexspi_storage
+        .configport_mut()
+        .configure_flexspi(&flexspi_config)
+        .unwrap();
+    flexspi_storage
+        .configport_mut()
+        .configure_device_port(&flash_config, &flexspi_config)
+        .unwrap();
+
+    // sequential-storage works on the async embedded-storage traits
+    let mut flash = BlockingAsync::new(MacronixDeviceDriver::new_blocking(
+        flexspi_storage,
+        macronix::CAPACITY,
+        macronix::GEOMETRY,
+    ));
+    let mut data_buffer = [0_u8; 128];
+
+    let boot_count = match fetch_item::<u8, u32, _>(
+        &mut flash,
+        STORAGE_RANGE,
+        &mut NoCache::new(),
+        &mut data_buffer,
+        &BOOT_COUNT_KEY,
+    )
+    .await
+    {
+        Ok(Some(count)) => count,
+        Ok(None) => {
+            info!("No boot count stored yet");
+            0
+        }
+        Err(_) => {
+            info!("Storage region not formatted, erasing");
+            erase_all(&mut flash, STORAGE_RANGE).await.unwrap();
+            0
+        }
+    };
+
+    info!("Boot count = {}", boot_count);
+
+    if store_item(
+        &mut flash,
+        STORAGE_RANGE,
+        &mut NoCache::new(),
+        &mut data_buffer,
+        &BOOT_COUNT_KEY,
+        &(boot_count + 1),
+    )
+    .await
+    .is_err()
+    {
+        error!("Failed to store boot count");
+    }
+
+    // Read the value back to confirm it survives the round trip
+    match fetch_item::<u8, u32, _>(
+        &mut flash,
+        STORAGE_RANGE,
+        &mut NoCache::new(),
+        &mut data_buffer,
+        &BOOT_COUNT_KEY,
+    )
+    .await
+    {
+        Ok(Some(count)) if count == boot_count + 1 => info!("Stored boot count = {}, reset to increment", count),
+        _ => error!("Boot count read back mismatch"),
+    }
+
+    loop {
+        Timer::after_millis(2000).await;
+    }
+}
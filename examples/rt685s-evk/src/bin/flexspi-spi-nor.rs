@@ -0,0 +1,418 @@
+#![no_std]
+#![no_main]
+
+use defmt::{error, info};
+use embassy_executor::Spawner;
+use embassy_imxrt::clocks;
+use embassy_imxrt::flexspi::nor::{
+    Blocking, FlexSpiFlashPort, FlexSpiFlashPortDeviceInstance, FlexspiConfig, FlexspiConfigPortData,
+    FlexspiDeviceConfig, FlexspiNorGeometry, FlexspiNorStorageBus,
+};
+use embassy_imxrt::pac::flexspi::flshcr1::Csintervalunit;
+use embassy_imxrt::pac::flexspi::flshcr2::Awrwaitunit;
+use embassy_imxrt::pac::flexspi::flshcr4::{Wmena, Wmenb};
+use embassy_time::Timer;
+use embedded_storage::nor_flash::{ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash};
+use storage_bus::nor::{
+    BlockingNorStorageBusDriver, NorStorageBusWidth, NorStorageCmd, NorStorageCmdMode, NorStorageCmdType,
+    NorStorageDummyCycles,
+};
+use {defmt_rtt as _, panic_probe as _};
+
+// Last sector of the first 16MB, reachable with 3 byte addressing
+const ADDR: u32 = 0xFF_F000;
+
+// Maximum number of bytes moved by a single IP command
+const MAX_TRANSFER_SIZE: usize = 128;
+
+// Standard single lane SPI NOR opcodes
+const CMD_READ: u8 = 0x03;
+const CMD_PAGE_PROGRAM: u8 = 0x02;
+const CMD_SECTOR_ERASE: u8 = 0x20;
+const CMD_READ_STATUS: u8 = 0x05;
+const CMD_WRITE_ENABLE: u8 = 0x06;
+const CMD_READ_JEDEC_ID: u8 = 0x9F;
+
+const STATUS_WIP: u8 = 0x01;
+
+// JEDEC SFDP (JESD216) layout
+const SFDP_SIGNATURE: u32 = 0x5044_4653; // "SFDP"
+const SFDP_BFPT_DWORDS: usize = 16;
+
+/// Plain SPI NOR flash driven over any NOR storage bus in single lane SDR mode
+struct SpiNorFlash<T: BlockingNorStorageBusDriver> {
+    storagebusdriver: T,
+    capacity: usize,
+    geometry: FlexspiNorGeometry,
+}
+
+#[derive(Debug)]
+enum SpiNorError {
+    /// The bus reported an error
+    Bus,
+    /// Access outside of the flash
+    OutOfBounds,
+    /// Access not aligned to a sector
+    NotAligned,
+}
+
+impl NorFlashError for SpiNorError {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            SpiNorError::Bus => NorFlashErrorKind::Other,
+            SpiNorError::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            SpiNorError::NotAligned => NorFlashErrorKind::NotAligned,
+        }
+    }
+}
+
+impl<T: BlockingNorStorageBusDriver> ErrorType for SpiNorFlash<T> {
+    type Error = SpiNorError;
+}
+
+// Single lane SDR command with an optional 3 byte address and data phase
+fn spi_cmd(opcode: u8, addr: Option<u32>, cmdtype: Option<NorStorageCmdType>, len: usize) -> NorStorageCmd {
+    NorStorageCmd {
+        cmd_lb: opcode,
+        cmd_ub: None,
+        addr,
+        addr_width: addr.map(|_| 24),
+        bus_width: NorStorageBusWidth::Single,
+        mode: NorStorageCmdMode::SDR,
+        dummy: NorStorageDummyCycles::Clocks(0),
+        data_bytes: cmdtype.as_ref().map(|_| len as u32),
+        cmdtype,
+    }
+}
+
+impl<T: BlockingNorStorageBusDriver> SpiNorFlash<T> {
+    fn new(storagebusdriver: T, capacity: usize, geometry: FlexspiNorGeometry) -> Self {
+        Self {
+            storagebusdriver,
+            capacity,
+            geometry,
+        }
+    }
+
+    fn check_range(&self, offset: u32, len: usize) -> Result<(), SpiNorError> {
+        match offset.checked_add(len as u32) {
+            Some(end) if end as usize <= self.capacity => Ok(()),
+            _ => Err(SpiNorError::OutOfBounds),
+        }
+    }
+
+    fn read_jedec_id(&mut self, id: &mut [u8; 3]) -> Result<(), SpiNorError> {
+        self.storagebusdriver
+            .send_command(
+                spi_cmd(CMD_READ_JEDEC_ID, None, Some(NorStorageCmdType::Read), id.len()),
+                Some(id),
+                None,
+            )
+            .map_err(|_| SpiNorError::Bus)
+    }
+
+    fn read_status(&mut self) -> Result<u8, SpiNorError> {
+        let mut status = [0_u8; 1];
+
+        self.storagebusdriver
+            .send_command(
+                spi_cmd(CMD_READ_STATUS, None, Some(NorStorageCmdType::Read), 1),
+                Some(&mut status),
+                None,
+            )
+            .map_err(|_| SpiNorError::Bus)?;
+
+        Ok(status[0])
+    }
+
+    fn write_enable(&mut self) -> Result<(), SpiNorError> {
+        self.storagebusdriver
+            .send_command(spi_cmd(CMD_WRITE_ENABLE, None, None, 0), None, None)
+            .map_err(|_| SpiNorError::Bus)
+    }
+
+    fn wait_for_idle(&mut self) -> Result<(), SpiNorError> {
+        while self.read_status()? & STATUS_WIP != 0 {}
+
+        Ok(())
+    }
+}
+
+impl<T: BlockingNorStorageBusDriver> ReadNorFlash for SpiNorFlash<T> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        self.check_range(offset, bytes.len())?;
+
+        for (i, chunk) in bytes.chunks_mut(MAX_TRANSFER_SIZE).enumerate() {
+            let addr = offset + (i * MAX_TRANSFER_SIZE) as u32;
+            let len = chunk.len();
+
+            self.storagebusdriver
+                .send_command(
+                    spi_cmd(CMD_READ, Some(addr), Some(NorStorageCmdType::Read), len),
+                    Some(chunk),
+                    None,
+                )
+                .map_err(|_| SpiNorError::Bus)?;
+        }
+
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl<T: BlockingNorStorageBusDriver> NorFlash for SpiNorFlash<T> {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = 4096;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        if from > to {
+            return Err(SpiNorError::OutOfBounds);
+        }
+        self.check_range(from, (to - from) as usize)?;
+
+        if from % Self::ERASE_SIZE as u32 != 0 || to % Self::ERASE_SIZE as u32 != 0 {
+            return Err(SpiNorError::NotAligned);
+        }
+
+        let mut addr = from;
+        while addr < to {
+            // Every erase clears the write enable latch, so set it again for each sector
+            self.write_enable()?;
+            self.storagebusdriver
+                .send_command(spi_cmd(CMD_SECTOR_ERASE, Some(addr), None, 0), None, None)
+                .map_err(|_| SpiNorError::Bus)?;
+            self.wait_for_idle()?;
+
+            addr += Self::ERASE_SIZE as u32;
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.check_range(offset, bytes.len())?;
+
+        let mut addr = offset;
+        let mut remaining = bytes;
+
+        while !remaining.is_empty() {
+            // Page program wraps inside the page, so never let a chunk cross a page boundary
+            let len = remaining
+                .len()
+                .min(self.geometry.page_remaining(addr) as usize)
+                .min(MAX_TRANSFER_SIZE);
+            let (chunk, rest) = remaining.split_at(len);
+
+            self.write_enable()?;
+            self.storagebusdriver
+                .send_command(
+                    spi_cmd(CMD_PAGE_PROGRAM, Some(addr), Some(NorStorageCmdType::Write), len),
+                    None,
+                    Some(chunk),
+                )
+                .map_err(|_| SpiNorError::Bus)?;
+            self.wait_for_idle()?;
+
+            addr += len as u32;
+            remaining = rest;
+        }
+
+        Ok(())
+    }
+}
+
+/// Discover capacity and geometry from the Basic Flash Parameter Table
+///
+/// Returns `None` when the flash has no SFDP tables or a field cannot be used by this driver.
+fn sfdp_geometry(bus: &mut FlexspiNorStorageBus<'_, Blocking>) -> Option<(usize, FlexspiNorGeometry)> {
+    // SFDP header followed by the first parameter header, which is always the BFPT
+    let mut header = [0_u8; 16];
+    bus.read_sfdp(0, &mut header).ok()?;
+    if u32::from_le_bytes([header[0], header[1], header[2], header[3]]) != SFDP_SIGNATURE {
+        return None;
+    }
+    info!("SFDP revision {}.{}", header[5], header[4]);
+
+    let len = (header[11] as usize).min(SFDP_BFPT_DWORDS);
+    let ptr = u32::from_le_bytes([header[12], header[13], header[14], 0]);
+    let mut raw = [0_u8; SFDP_BFPT_DWORDS * 4];
+    bus.read_sfdp(ptr, &mut raw[..len * 4]).ok()?;
+    let dword = |n: usize| u32::from_le_bytes([raw[n * 4], raw[n * 4 + 1], raw[n * 4 + 2], raw[n * 4 + 3]]);
+
+    // Density in bits, capped to what 3 byte addressing can reach
+    let density = dword(1);
+    let capacity = if density & 0x8000_0000 == 0 {
+        (density as usize + 1) / 8
+    } else {
+        1_usize.checked_shl((density & 0x7FFF_FFFF).checked_sub(3)?)?
+    };
+
+    // Erase types 1-4, size as a power of two and opcode. The sector is the one erased with
+    // the opcode this driver issues, the block the largest one.
+    let mut sector_size = None;
+    let mut block_size = 0;
+    for i in 0..4 {
+        let pair = (dword(7 + i / 2) >> ((i % 2) * 16)) as u16;
+        let (size_exp, opcode) = (pair as u8, (pair >> 8) as u8);
+        if size_exp == 0 {
+            continue;
+        }
+        if opcode == CMD_SECTOR_ERASE {
+            sector_size = Some(1_u32 << size_exp);
+        }
+        block_size = block_size.max(1_u32 << size_exp);
+    }
+
+    // Page size is only present from JESD216A on (11 or more DWORDs)
+    let page_size = if len >= 11 { 1 << ((dword(10) >> 4) & 0xF) } else { 256 };
+
+    Some((
+        capacity.min(0x100_0000),
+        FlexspiNorGeometry {
+            page_size,
+            sector_size: sector_size?,
+            block_size,
+        },
+    ))
+}
+
+#[embassy_executor::main]
+async fn main(_spawner: Spawner) {
+    let p = embassy_imxrt::init(Default::default());
+
+    // The flash on the other end must be in plain SPI mode, e.g. a serial NOR straight out of
+    // power on reset. Only DATA0 (MOSI) and DATA1 (MISO) are used.
+
+    let flash_config = FlexspiDeviceConfig {
+        // Root clock as configured by clock init (FFRO)
+        flexspi_root_clk: clocks::flexspi_clock_rate().unwrap(),
+        is_sck2_enabled: false,
+        // Flash size in this struct is in KB, so divide by 1KB
+        flash_size_kb: 0x4000, // 16 MB
+        cs_interval_unit: Csintervalunit::Csintervalunit0,
+        cs_interval: 0,
+        cs_hold_time: 3,
+        cs_setup_time: 3,
+        data_valid_time: 2,
+        columnspace: 0,
+        enable_word_address: false,
+        awr_seq_index: 0,
+        awr_seq_number: 0,
+        ard_seq_index: 0,
+        ard_seq_number: 0,
+        ahb_write_wait_unit: Awrwaitunit::Awrwaitunit2,
+        ahb_write_wait_interval: 0,
+        enable_write_mask_port_a: Wmena::Wmena0,
+        enable_write_mask_port_b: Wmenb::Wmenb0,
+    };
+    let flexspi_config = FlexspiConfig::default();
+
+    let mut flexspi_storage = FlexspiNorStorageBus::new_blocking_single_config(
+        p.FLEXSPI, // FlexSPI peripheral
+        p.PIO1_11, // DATA0 / MOSI
+        p.PIO1_12, // DATA1 / MISO
+        p.PIO1_29, // SCLK
+        p.PIO2_19, // CS0
+        FlexspiConfigPortData {
+            port: FlexSpiFlashPort::PortB,
+            dev_instance: FlexSpiFlashPortDeviceInstance::DeviceInstance0,
+            rx_watermark: 0x8,
+            tx_watermark: 0x8,
+            read_only: false,
+        },
+    );
+
+    let configured = flexspi_storage
+        .configport_mut()
+        .configure_flexspi(&flexspi_config)
+        .and_then(|_| {
+            flexspi_storage
+                .configport_mut()
+                .configure_device_port(&flash_config, &flexspi_config)
+        });
+    if let Err(e) = configured {
+        error!("Failed to configure FlexSPI: {}", e);
+        loop {
+            Timer::after_millis(2000).await;
+        }
+    }
+
+    // Fall back to a common 16MB part when the flash cannot describe itself
+    let (capacity, geometry) = match sfdp_geometry(&mut flexspi_storage) {
+        Some((capacity, geometry)) => {
+            info!(
+                "SFDP: capacity {} bytes, page {}, sector {}, block {}",
+                capacity, geometry.page_size, geometry.sector_size, geometry.block_size
+            );
+            (capacity, geometry)
+        }
+        None => {
+            error!("No usable SFDP tables, using default geometry");
+            (
+                0x100_0000,
+                FlexspiNorGeometry {
+                    page_size: 256,
+                    sector_size: 4096,
+                    block_size: 0x10000,
+                },
+            )
+        }
+    };
+    let mut flash = SpiNorFlash::new(flexspi_storage, capacity, geometry);
+
+    let mut jedec_id = [0_u8; 3];
+    match flash.read_jedec_id(&mut jedec_id) {
+        Ok(()) => info!("Jedec ID = {:02X}", jedec_id),
+        Err(e) => error!("Failed to read Jedec ID: {}", defmt::Debug2Format(&e)),
+    }
+
+    let mut write_data = [0_u8; 32];
+    for (i, item) in write_data.iter_mut().enumerate() {
+        *item = i as u8;
+    }
+    let mut read_data = [0_u8; 32];
+
+    let result = flash
+        .erase(ADDR, ADDR + geometry.sector_size)
+        .and_then(|_| flash.write(ADDR, &write_data))
+        .and_then(|_| flash.read(ADDR, &mut read_data));
+
+    match result {
+        Ok(()) if read_data == write_data => info!("Read back matches written data"),
+        Ok(()) => error!("Read back mismatch: {:02X}", read_data),
+        Err(e) => error!("SPI NOR access failed: {}", defmt::Debug2Format(&e)),
+    }
+
+    // Programs ending in a partial TX FIFO word, a partial and a full IP transfer and a full
+    // page. The byte after each one must still be erased, i.e. the zero padding of the last
+    // FIFO word never reaches the flash. The pattern avoids 0xFF so that check is meaningful.
+    let mut pattern = [0_u8; 256];
+    for (i, item) in pattern.iter_mut().enumerate() {
+        *item = (i % 0xFF) as u8;
+    }
+    for len in [1, 3, 255, 256] {
+        let mut read_back = [0_u8; 257];
+
+        let result = flash
+            .erase(ADDR, ADDR + geometry.sector_size)
+            .and_then(|_| flash.write(ADDR, &pattern[..len]))
+            .and_then(|_| flash.read(ADDR, &mut read_back[..len + 1]));
+
+        match result {
+            Ok(()) if read_back[..len] == pattern[..len] && read_back[len] == 0xFF => {
+                info!("{} byte write reads back", len)
+            }
+            Ok(()) => error!("{} byte write read back mismatch: {:02X}", len, &read_back[..len + 1]),
+            Err(e) => error!("{} byte write failed: {}", len, defmt::Debug2Format(&e)),
+        }
+    }
+
+    loop {
+        Timer::after_millis(2000).await;
+    }
+}
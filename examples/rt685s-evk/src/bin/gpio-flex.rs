@@ -84,11 +84,28 @@ async fn main(_spawner: Spawner) {
     // toggle pin
     flex.toggle();
 
-    let flex = flex.enable_sensing();
+    let mut flex = flex.enable_sensing();
 
     // check pin level is high
     assert!(flex.is_high());
 
+    // switch to pseudo open-drain, as for a wire-OR'd reset line: low is driven, high releases
+    // the pin to the pull-up of the net
+    flex.set_as_output(
+        gpio::DriveMode::OpenDrain,
+        gpio::DriveStrength::Normal,
+        gpio::SlewRate::Standard,
+    );
+
+    // assert the line
+    flex.set_low();
+
+    // check pin level is low
+    assert!(flex.is_low());
+
+    // release the line again
+    flex.set_high();
+
     loop {
         Timer::after_millis(1000).await;
     }
@@ -4,8 +4,8 @@
 use defmt::{error, info};
 use embassy_executor::Spawner;
 use embassy_imxrt::{bind_interrupts, i2c, peripherals};
-use embassy_time::Timer;
-use embedded_hal_async::i2c::I2c;
+use embassy_time::{with_timeout, Duration, Timer};
+use embedded_hal_async::i2c::{I2c, Operation};
 use {defmt_rtt as _, embassy_imxrt_examples as _, panic_probe as _};
 
 const NACK_ADDR: u8 = 0x07;
@@ -16,6 +16,12 @@ const ACC_ID_REG: u8 = 0x0D;
 const ACC_CTRL_REG: u8 = 0x2A;
 const ACC_XYZ_DATA_CFG_REG: u8 = 0x0E;
 const ACC_STATUS_REG: u8 = 0x00;
+const ACC_CTRL_REG4: u8 = 0x2D;
+const ACC_CTRL_REG5: u8 = 0x2E;
+
+// Data-ready interrupt enable, routed to INT1
+const ACC_INT_EN_DRDY: u8 = 0x01;
+const ACC_INT_CFG_DRDY_INT1: u8 = 0x01;
 
 const ACC_ID: u8 = 0xC7;
 const ACC_STATUS_DATA_READY: u8 = 0xFF;
@@ -61,7 +67,7 @@ async fn main(_spawner: Spawner) {
     // Input function is not inverted
     info!("Configuring GPIO1_7 as output");
     info!("Configuring GPIO1_7 as low");
-    let mut _reset_pin = Output::new(
+    let mut reset_pin = Output::new(
         p.PIO1_7,
         Level::Low,
         DriveMode::PushPull,
@@ -69,6 +75,17 @@ async fn main(_spawner: Spawner) {
         SlewRate::Standard,
     );
 
+    // Pulse the active high reset line to start the accelerometer from a known state
+    info!("Pulsing ACC reset");
+    reset_pin.toggle();
+    Timer::after_millis(1).await;
+    reset_pin.toggle();
+    if reset_pin.is_set_low() {
+        info!("ACC reset released");
+    }
+    // The accelerometer needs about 1 ms to boot after reset
+    Timer::after_millis(1).await;
+
     // Set GPIO1_5 (Interrupt) as input
     // Configure IO Pad Control 1_5 for ACC Interrupt Pin
     //
@@ -82,12 +99,16 @@ async fn main(_spawner: Spawner) {
     // Pseudo Output Drain is disabled
     // Input function is not inverted
     info!("Configuring GPIO1_5 as input");
-    let _isr_pin = Input::new(p.PIO1_5, Pull::Down, Inverter::Disabled);
+    let mut isr_pin = Input::new(p.PIO1_5, Pull::Down, Inverter::Disabled);
 
     info!("i2c example - I2c::new");
+    // Fail transfers with a timeout instead of hanging if the accelerometer holds the bus
+    let config = i2c::master::Config {
+        timeout_us: Some(10_000),
+        ..Default::default()
+    };
     let mut i2c =
-        i2c::master::I2cMaster::new_async(p.FLEXCOMM2, p.PIO0_18, p.PIO0_17, Irqs, Default::default(), p.DMA0_CH5)
-            .unwrap();
+        i2c::master::I2cMaster::new_async(p.FLEXCOMM2, p.PIO0_18, p.PIO0_17, Irqs, config, p.DMA0_CH5).unwrap();
 
     info!("i2c example - write nack check");
     let result = i2c.write(NACK_ADDR, &[ACC_ID_REG]).await;
@@ -158,6 +179,16 @@ async fn main(_spawner: Spawner) {
         error!("i2c example - Error xyz data cfg reg {}", result.unwrap_err());
     }
 
+    // Enable the data-ready interrupt on INT1 (active low), only writable in standby
+    info!("i2c example - Enable ACC data-ready interrupt");
+    let mut result = i2c.write(ACC_ADDR, &[ACC_CTRL_REG4, ACC_INT_EN_DRDY]).await;
+    if result.is_ok() {
+        result = i2c.write(ACC_ADDR, &[ACC_CTRL_REG5, ACC_INT_CFG_DRDY_INT1]).await;
+    }
+    if let Err(e) = result {
+        error!("i2c example - Error enabling data-ready interrupt {}", e);
+    }
+
     // Write 0x0D to accelerometer control register
     /*  [7-6]: aslp_rate=00 */
     /*  [5-3]: dr=001 for 200Hz data rate (when in hybrid mode) */
@@ -180,11 +211,15 @@ async fn main(_spawner: Spawner) {
     let mut reg = [0u8; 1];
     reg[0] = 0xAA;
     while reg[0] != ACC_STATUS_DATA_READY {
-        let result = i2c.write_read(ACC_ADDR, &[ACC_STATUS_REG], &mut reg).await;
+        // Give up instead of polling forever when the sensor is not populated
+        let result = i2c
+            .write_read_with_timeout(ACC_ADDR, &[ACC_STATUS_REG], &mut reg, Duration::from_millis(10))
+            .await;
         if result.is_ok() {
             info!("i2c example - Read status register: {:02X}", reg[0]);
         } else {
             error!("i2c example - Error reading status register {}", result.unwrap_err());
+            return;
         }
         Timer::after_millis(100).await;
     }
@@ -192,6 +227,14 @@ async fn main(_spawner: Spawner) {
     /* Accelerometer status register, first byte always 0xFF, then X:Y:Z each 2 bytes, in total 7 bytes */
     info!("i2c example - Read XYZ data from ACC status register");
     for _ in 0..10 {
+        // Reading the XYZ data deasserts the data-ready interrupt
+        if with_timeout(Duration::from_millis(100), isr_pin.wait_for_low())
+            .await
+            .is_err()
+        {
+            error!("i2c example - Timed out waiting for data-ready interrupt");
+        }
+
         let mut reg: [u8; 7] = [0xAA; 7];
         let result = i2c.write_read(ACC_ADDR, &[ACC_STATUS_REG], &mut reg).await;
         if result.is_ok() {
@@ -201,6 +244,58 @@ async fn main(_spawner: Spawner) {
         }
     }
 
+    // Read back the control register, rewrite it and check the value stuck
+    info!("i2c example - read/rewrite transactions on ACC control register");
+    let mut ctrl = [0u8; 1];
+    let result = i2c
+        .transaction(
+            ACC_ADDR,
+            &mut [Operation::Write(&[ACC_CTRL_REG]), Operation::Read(&mut ctrl)],
+        )
+        .await;
+    if result.is_ok() {
+        // Adjacent writes are merged into one transfer, so the rewrite and the
+        // read back need a transaction each
+        let write_back = [ACC_CTRL_REG, ctrl[0]];
+        let mut verify = [0u8; 1];
+        let mut result = i2c.transaction(ACC_ADDR, &mut [Operation::Write(&write_back)]).await;
+        if result.is_ok() {
+            result = i2c
+                .transaction(
+                    ACC_ADDR,
+                    &mut [Operation::Write(&[ACC_CTRL_REG]), Operation::Read(&mut verify)],
+                )
+                .await;
+        }
+        if result.is_ok() && verify[0] == ctrl[0] {
+            info!("i2c example - transaction read back ctrl reg: {:02X}", verify[0]);
+        } else {
+            error!("i2c example - Error in ctrl reg transaction {}", result);
+        }
+    } else {
+        error!(
+            "i2c example - Error reading ctrl reg in transaction {}",
+            result.unwrap_err()
+        );
+    }
+
+    info!("i2c example - transaction nack check");
+    let mut reg = [0u8; 1];
+    let result = i2c
+        .transaction(
+            NACK_ADDR,
+            &mut [Operation::Write(&[ACC_ID_REG]), Operation::Read(&mut reg)],
+        )
+        .await;
+    if result.is_err_and(|e| e == i2c::TransferError::AddressNack.into()) {
+        info!("i2c example - transaction nack check gets the right error");
+    } else {
+        error!(
+            "i2c example - transaction nack check error did not get the error {}",
+            result
+        );
+    }
+
     info!("i2c example - Done!  Busy Loop...");
     loop {
         Timer::after_millis(1000).await;
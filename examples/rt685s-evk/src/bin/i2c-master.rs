@@ -55,7 +55,7 @@ async fn main(_spawner: Spawner) {
     // Input function is not inverted
     info!("Configuring GPIO1_7 as output");
     info!("Configuring GPIO1_7 as low");
-    let mut _reset_pin = Output::new(
+    let mut reset_pin = Output::new(
         p.PIO1_7,
         Level::Low,
         DriveMode::PushPull,
@@ -63,6 +63,17 @@ async fn main(_spawner: Spawner) {
         SlewRate::Standard,
     );
 
+    // Pulse the active high reset line to start the accelerometer from a known state
+    info!("Pulsing ACC reset");
+    reset_pin.toggle();
+    Timer::after_millis(1).await;
+    reset_pin.toggle();
+    if reset_pin.is_set_low() {
+        info!("ACC reset released");
+    }
+    // The accelerometer needs about 1 ms to boot after reset
+    Timer::after_millis(1).await;
+
     // Set GPIO1_5 (Interrupt) as input
     // Configure IO Pad Control 1_5 for ACC Interrupt Pin
     //
@@ -1,5 +1,7 @@
 #![no_std]
 
+pub mod macronix;
+
 use mimxrt600_fcb::FlexSPIFlashConfigurationBlock;
 
 // auto-generated version information from Cargo.toml
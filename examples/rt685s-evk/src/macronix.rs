@@ -0,0 +1,587 @@
+//! Driver and board configuration of the Macronix MX25UM51345G octal NOR flash on the EVK,
+//! shared by the FlexSPI storage examples
+
+use defmt::info;
+use embassy_imxrt::clocks;
+use embassy_imxrt::flexspi::nor::{FlexspiDeviceConfig, FlexspiNorGeometry};
+use embassy_imxrt::pac::flexspi::flshcr1::Csintervalunit;
+use embassy_imxrt::pac::flexspi::flshcr2::Awrwaitunit;
+use embassy_imxrt::pac::flexspi::flshcr4::{Wmena, Wmenb};
+use embassy_time::{block_for, Duration};
+use embedded_storage::nor_flash::{
+    ErrorType, MultiwriteNorFlash, NorFlash as BlockingNorFlash, NorFlashError, NorFlashErrorKind,
+    ReadNorFlash as BlockingReadNorFlash,
+};
+use storage_bus::nor::{
+    BlockingNorStorageBusDriver, NorStorageBusError, NorStorageBusWidth, NorStorageCmd, NorStorageCmdMode,
+    NorStorageCmdType, NorStorageDummyCycles,
+};
+
+/// Size of the flash in bytes, 64MB
+pub const CAPACITY: usize = 0x400_0000;
+
+/// Page, sector and block sizes of the flash
+pub const GEOMETRY: FlexspiNorGeometry = FlexspiNorGeometry {
+    page_size: 256,
+    sector_size: 4096,
+    block_size: 0x10000,
+};
+
+/// FlexSPI device port configuration for the flash on Port B
+pub fn device_config() -> FlexspiDeviceConfig {
+    FlexspiDeviceConfig {
+        // Root clock as configured by clock init (FFRO)
+        flexspi_root_clk: clocks::flexspi_clock_rate().unwrap(),
+        is_sck2_enabled: false,
+        // Flash size in this struct is in KB, so divide by 1KB
+        flash_size_kb: (CAPACITY / 1024) as u32,
+        cs_interval_unit: Csintervalunit::Csintervalunit0,
+        cs_interval: 0,
+        cs_hold_time: 3,
+        cs_setup_time: 3,
+        data_valid_time: 2,
+        columnspace: 0,
+        enable_word_address: false,
+        awr_seq_index: 0,
+        awr_seq_number: 0,
+        ard_seq_index: 0,
+        ard_seq_number: 0,
+        ahb_write_wait_unit: Awrwaitunit::Awrwaitunit2,
+        ahb_write_wait_interval: 0,
+        enable_write_mask_port_a: Wmena::Wmena0,
+        enable_write_mask_port_b: Wmenb::Wmenb0,
+    }
+}
+
+// Maximum number of bytes moved by a single read command
+const MAX_READ_SIZE: usize = 128;
+
+// Maximum number of bytes moved by a single page program command
+const MAX_WRITE_SIZE: usize = 128;
+
+mod sealed {
+    /// simply seal a trait
+    pub trait Sealed {}
+}
+
+impl sealed::Sealed for Blocking {}
+impl sealed::Sealed for Async {}
+
+/// Driver mode.
+#[allow(private_bounds)]
+pub trait Mode: sealed::Sealed {}
+
+/// Blocking mode.
+pub struct Blocking;
+impl Mode for Blocking {}
+
+/// Async mode.
+pub struct Async;
+impl Mode for Async {}
+
+/// Driver for the Macronix MX25UM51345G octal NOR flash, on top of any NOR storage bus
+pub struct MacronixDeviceDriver<T: BlockingNorStorageBusDriver, M: Mode> {
+    // Bus driver dependency
+    storagebusdriver: T,
+    capacity: usize,
+    geometry: FlexspiNorGeometry,
+    // Maximum number of status polls while waiting for erase/program, unbounded if None
+    wip_poll_limit: Option<u32>,
+    _phantom: core::marker::PhantomData<M>,
+}
+
+#[derive(Debug)]
+/// Errors reported by [`MacronixDeviceDriver`]
+pub enum NorErrorType {
+    /// Error reported by the storage bus while talking to the flash
+    FlashStorageErrorBus(NorStorageBusError),
+
+    /// Error object for overflow errror
+    FlashStorageErrorOutOfBounds,
+
+    /// Error object for alignment error
+    FlashStorageErrorNotAligned,
+
+    /// Error object for an operation that did not complete in the allowed number of status polls
+    FlashStorageErrorTimeout,
+
+    /// Error object for data that did not read back as programmed
+    FlashStorageErrorVerify,
+
+    /// Error object for an erase unit size the flash has no erase command for
+    FlashStorageErrorUnsupported,
+}
+
+impl<T: BlockingNorStorageBusDriver, M: Mode> ErrorType for MacronixDeviceDriver<T, M> {
+    type Error = NorErrorType;
+}
+
+impl NorFlashError for NorErrorType {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            NorErrorType::FlashStorageErrorBus(_) => NorFlashErrorKind::Other,
+            NorErrorType::FlashStorageErrorOutOfBounds => NorFlashErrorKind::OutOfBounds,
+            NorErrorType::FlashStorageErrorNotAligned => NorFlashErrorKind::NotAligned,
+            NorErrorType::FlashStorageErrorTimeout => NorFlashErrorKind::Other,
+            NorErrorType::FlashStorageErrorVerify => NorFlashErrorKind::Other,
+            NorErrorType::FlashStorageErrorUnsupported => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+impl<T: BlockingNorStorageBusDriver> MacronixDeviceDriver<T, Blocking> {
+    /// Read the manufacturer, memory type and capacity bytes of the JEDEC ID
+    pub fn read_jedec_id(&mut self) -> Result<[u8; 3], NorErrorType> {
+        // Octal DDR transfers move whole 16 bit words, so read 4 bytes and drop the last one
+        let mut jedec = [0_u8; 4];
+
+        let read_cread_jedec_id_cmd = NorStorageCmd {
+            cmd_lb: 0x9F,
+            cmd_ub: Some(0x60),
+            addr: Some(0x0),
+            addr_width: Some(32),
+            bus_width: NorStorageBusWidth::Octal, // 3 - Octal
+            mode: NorStorageCmdMode::DDR,
+            dummy: NorStorageDummyCycles::Clocks(0x18),
+            cmdtype: Some(NorStorageCmdType::Read),
+            data_bytes: Some(4),
+        };
+
+        self.storagebusdriver
+            .send_command(read_cread_jedec_id_cmd, Some(&mut jedec), None)
+            .map_err(NorErrorType::FlashStorageErrorBus)?;
+
+        Ok([jedec[0], jedec[1], jedec[2]])
+    }
+}
+
+impl<T: BlockingNorStorageBusDriver> BlockingReadNorFlash for MacronixDeviceDriver<T, Blocking> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Self::Error> {
+        let mut read_start_ptr = 0;
+
+        self.check_range(offset, bytes.len())?;
+
+        // A single IP command moves at most MAX_READ_SIZE bytes, so split larger buffers
+        while read_start_ptr < bytes.len() {
+            let len = (bytes.len() - read_start_ptr).min(MAX_READ_SIZE);
+
+            // Read data from the storage device
+            let read_cmd = NorStorageCmd {
+                cmd_lb: 0xEE,
+                cmd_ub: Some(0x11),
+                addr: Some(offset + read_start_ptr as u32),
+                addr_width: Some(0x20),
+                bus_width: NorStorageBusWidth::Octal,
+                mode: NorStorageCmdMode::DDR,
+                dummy: NorStorageDummyCycles::Clocks(0x29),
+                cmdtype: Some(NorStorageCmdType::Read),
+                data_bytes: Some(len as u32),
+            };
+
+            self.storagebusdriver
+                .send_command(read_cmd, Some(&mut bytes[read_start_ptr..read_start_ptr + len]), None)
+                .map_err(NorErrorType::FlashStorageErrorBus)?;
+
+            read_start_ptr += len;
+        }
+
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        self.capacity
+    }
+}
+
+impl<T: BlockingNorStorageBusDriver> BlockingNorFlash for MacronixDeviceDriver<T, Blocking> {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = 4096;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Self::Error> {
+        info!("Erase data");
+
+        // ERASE_SIZE is only the smallest sector any supported part has, the device geometry
+        // decides the real sector size
+        self.erase_units(from, to, self.sector_size())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.check_range(offset, bytes.len())?;
+
+        let mut addr = offset;
+        let mut remaining = bytes;
+
+        while !remaining.is_empty() {
+            // Page program wraps inside the page, so never let a chunk cross a page boundary
+            let len = remaining.len().min(self.geometry.page_remaining(addr) as usize);
+            let (chunk, rest) = remaining.split_at(len);
+
+            self.page_program(addr, chunk)?;
+
+            addr += len as u32;
+            remaining = rest;
+        }
+
+        Ok(())
+    }
+}
+
+// Page program only clears bits and the part has no ECC over program units, so an already
+// programmed location can be written again as long as bits only go from 1 to 0
+impl<T: BlockingNorStorageBusDriver> MultiwriteNorFlash for MacronixDeviceDriver<T, Blocking> {}
+
+impl<T: BlockingNorStorageBusDriver> MacronixDeviceDriver<T, Blocking> {
+    /// Program `data` at `addr` through the command port and wait for the flash to finish
+    ///
+    /// `data` must not cross a page boundary. It is sent as one or more page program
+    /// commands of at most MAX_WRITE_SIZE bytes each.
+    pub fn page_program(&mut self, addr: u32, data: &[u8]) -> Result<(), NorErrorType> {
+        self.check_range(addr, data.len())?;
+        if data.len() as u32 > self.geometry.page_remaining(addr) {
+            return Err(NorErrorType::FlashStorageErrorNotAligned);
+        }
+
+        for (i, chunk) in data.chunks(MAX_WRITE_SIZE).enumerate() {
+            // Enable Write
+            let write_enable_cmd = NorStorageCmd {
+                cmd_lb: 0x06,
+                cmd_ub: Some(0xF9),
+                addr: None,
+                addr_width: None,
+                bus_width: NorStorageBusWidth::Octal,
+                mode: NorStorageCmdMode::DDR,
+                dummy: NorStorageDummyCycles::Clocks(0),
+                cmdtype: None,
+                data_bytes: None,
+            };
+            self.storagebusdriver
+                .send_command(write_enable_cmd, None, None)
+                .map_err(NorErrorType::FlashStorageErrorBus)?;
+
+            // Page Program
+            let write_cmd = NorStorageCmd {
+                cmd_lb: 0x12,
+                cmd_ub: Some(0xED),
+                addr: Some(addr + (i * MAX_WRITE_SIZE) as u32),
+                addr_width: Some(0x20),
+                bus_width: NorStorageBusWidth::Octal,
+                mode: NorStorageCmdMode::DDR,
+                dummy: NorStorageDummyCycles::Clocks(0),
+                cmdtype: Some(NorStorageCmdType::Write),
+                data_bytes: Some(chunk.len() as u32),
+            };
+            self.storagebusdriver
+                .send_command(write_cmd, None, Some(chunk))
+                .map_err(NorErrorType::FlashStorageErrorBus)?;
+
+            self.wait_for_idle()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sector size of the device in bytes, the smallest erasable unit
+    pub fn sector_size(&self) -> u32 {
+        self.geometry.sector_size
+    }
+
+    /// Block size of the device in bytes
+    pub fn block_size(&self) -> u32 {
+        self.geometry.block_size
+    }
+
+    /// Erase `[from, to)` block by block
+    ///
+    /// Both bounds must be aligned to [`Self::block_size`]. Erasing a large range block by
+    /// block is much faster than going through [`BlockingNorFlash::erase`] sector by sector.
+    pub fn erase_blocks(&mut self, from: u32, to: u32) -> Result<(), NorErrorType> {
+        self.erase_units(from, to, self.block_size())
+    }
+
+    /// Erase the sectors covering `[addr, addr + data.len())`, program `data` and read it back
+    ///
+    /// The rest of the affected sectors is erased as well. Returns `FlashStorageErrorVerify`
+    /// when the flash does not read back `data`.
+    pub fn program_verify(&mut self, addr: u32, data: &[u8]) -> Result<(), NorErrorType> {
+        let end = addr
+            .checked_add(data.len() as u32)
+            .ok_or(NorErrorType::FlashStorageErrorOutOfBounds)?;
+        let sector_size = self.sector_size();
+        let from = self.geometry.sector_base(self.geometry.sector_of(addr));
+        self.erase_units(from, end.next_multiple_of(sector_size), sector_size)?;
+
+        self.write(addr, data)?;
+
+        let mut buf = [0_u8; MAX_READ_SIZE];
+        for (i, chunk) in data.chunks(MAX_READ_SIZE).enumerate() {
+            let read = &mut buf[..chunk.len()];
+            self.read(addr + (i * MAX_READ_SIZE) as u32, read)?;
+
+            if read != chunk {
+                return Err(NorErrorType::FlashStorageErrorVerify);
+            }
+        }
+
+        Ok(())
+    }
+
+    // Reject accesses past the end of the flash, without letting `offset + len` wrap around
+    fn check_range(&self, offset: u32, len: usize) -> Result<(), NorErrorType> {
+        match offset.checked_add(len as u32) {
+            Some(end) if end as usize <= self.capacity => Ok(()),
+            _ => Err(NorErrorType::FlashStorageErrorOutOfBounds),
+        }
+    }
+
+    // Erase command for `unit` sized regions: sector erase (SE4B) for 4KB, block erase (BE4B)
+    // for 64KB. The part has no octal DDR command for other sizes.
+    fn erase_opcode(unit: u32) -> Result<u8, NorErrorType> {
+        match unit {
+            0x1000 => Ok(0x21),
+            0x10000 => Ok(0xDC),
+            _ => Err(NorErrorType::FlashStorageErrorUnsupported),
+        }
+    }
+
+    // Erase every `unit` sized region in [from, to), waiting for each erase
+    fn erase_units(&mut self, from: u32, to: u32, unit: u32) -> Result<(), NorErrorType> {
+        let opcode = Self::erase_opcode(unit)?;
+
+        if from > to {
+            return Err(NorErrorType::FlashStorageErrorOutOfBounds);
+        }
+
+        self.check_range(from, (to - from) as usize)?;
+
+        if from % unit != 0 {
+            return Err(NorErrorType::FlashStorageErrorNotAligned);
+        }
+
+        if to % unit != 0 {
+            return Err(NorErrorType::FlashStorageErrorNotAligned);
+        }
+
+        // `to` is exclusive, so erase every unit in [from, to)
+        for addr in (from..to).step_by(unit as usize) {
+            // The write enable latch is cleared after each erase, so set it for every unit
+            self.send_simple_cmd(0x06)?;
+
+            self.storagebusdriver
+                .send_command(
+                    NorStorageCmd {
+                        cmd_lb: opcode,
+                        cmd_ub: Some(!opcode),
+                        addr: Some(addr),
+                        addr_width: Some(0x20),
+                        bus_width: NorStorageBusWidth::Octal,
+                        mode: NorStorageCmdMode::DDR,
+                        dummy: NorStorageDummyCycles::Clocks(0),
+                        cmdtype: None,
+                        data_bytes: None,
+                    },
+                    None,
+                    None,
+                )
+                .map_err(NorErrorType::FlashStorageErrorBus)?;
+            self.wait_for_idle()?;
+        }
+
+        Ok(())
+    }
+
+    // Octal DDR command without address or data phase, sent as the opcode and its inverse
+    fn send_simple_cmd(&mut self, opcode: u8) -> Result<(), NorErrorType> {
+        let cmd = NorStorageCmd {
+            cmd_lb: opcode,
+            cmd_ub: Some(!opcode),
+            addr: None,
+            addr_width: None,
+            bus_width: NorStorageBusWidth::Octal,
+            mode: NorStorageCmdMode::DDR,
+            dummy: NorStorageDummyCycles::Clocks(0),
+            cmdtype: None,
+            data_bytes: None,
+        };
+        self.storagebusdriver
+            .send_command(cmd, None, None)
+            .map_err(NorErrorType::FlashStorageErrorBus)
+    }
+
+    // Write enable followed by a register write at `addr`, waiting for the write to complete
+    fn write_register(&mut self, opcode: u8, addr: u32, value: &[u8]) -> Result<(), NorErrorType> {
+        self.send_simple_cmd(0x06)?;
+
+        let write_reg_cmd = NorStorageCmd {
+            cmd_lb: opcode,
+            cmd_ub: Some(!opcode),
+            addr: Some(addr),
+            addr_width: Some(0x20),
+            bus_width: NorStorageBusWidth::Octal,
+            mode: NorStorageCmdMode::DDR,
+            dummy: NorStorageDummyCycles::Clocks(0),
+            cmdtype: Some(NorStorageCmdType::Write),
+            data_bytes: Some(value.len() as u32),
+        };
+        self.storagebusdriver
+            .send_command(write_reg_cmd, None, Some(value))
+            .map_err(NorErrorType::FlashStorageErrorBus)?;
+
+        self.wait_for_idle()
+    }
+
+    /// Write the status register (WRSR, 0x01)
+    ///
+    /// Octal DDR transfers move whole 16 bit words, so pass the value twice, e.g. `[v, v]`.
+    pub fn write_status_reg(&mut self, value: &[u8]) -> Result<(), NorErrorType> {
+        self.write_register(0x01, 0, value)
+    }
+
+    /// Write configuration register 2 at `addr` (WRCR2, 0x72)
+    ///
+    /// Configuration register 2 holds e.g. the SPI/OPI mode and dummy cycle settings. As with
+    /// [`Self::write_status_reg`] the value is sent as a 16 bit word.
+    pub fn write_cfg_reg2(&mut self, addr: u32, value: &[u8]) -> Result<(), NorErrorType> {
+        self.write_register(0x72, addr, value)
+    }
+
+    /// Software reset the flash with Reset Enable (0x66) followed by Reset Memory (0x99)
+    ///
+    /// The part comes out of reset in plain SPI mode, so it has to be switched back to octal
+    /// DDR before any other command of this driver is used.
+    pub fn reset_device(&mut self) -> Result<(), NorErrorType> {
+        self.send_simple_cmd(0x66)?;
+        self.send_simple_cmd(0x99)?;
+
+        // tREADY2, worst case when the reset interrupts an erase
+        block_for(Duration::from_millis(25));
+
+        Ok(())
+    }
+
+    /// Put the flash into deep power-down (0xB9)
+    ///
+    /// Only Release from Deep Power-down is accepted until [`Self::power_up`] is called.
+    pub fn power_down(&mut self) -> Result<(), NorErrorType> {
+        self.send_simple_cmd(0xB9)?;
+
+        // tDP
+        block_for(Duration::from_micros(10));
+
+        Ok(())
+    }
+
+    /// Release the flash from deep power-down (0xAB)
+    pub fn power_up(&mut self) -> Result<(), NorErrorType> {
+        self.send_simple_cmd(0xAB)?;
+
+        // tRES1
+        block_for(Duration::from_micros(30));
+
+        Ok(())
+    }
+
+    /// Erase the whole flash with a single chip erase command and wait for it to finish
+    ///
+    /// Chip erase takes far longer than a sector erase, so bound the wait with
+    /// [`Self::set_wip_poll_limit`] generously or leave it unbounded.
+    pub fn chip_erase(&mut self) -> Result<(), NorErrorType> {
+        // Enable Write
+        let write_enable_cmd = NorStorageCmd {
+            cmd_lb: 0x06,
+            cmd_ub: Some(0xF9),
+            addr: None,
+            addr_width: None,
+            bus_width: NorStorageBusWidth::Octal,
+            mode: NorStorageCmdMode::DDR,
+            dummy: NorStorageDummyCycles::Clocks(0),
+            cmdtype: None,
+            data_bytes: None,
+        };
+        self.storagebusdriver
+            .send_command(write_enable_cmd, None, None)
+            .map_err(NorErrorType::FlashStorageErrorBus)?;
+
+        // Chip Erase
+        let chip_erase_cmd = NorStorageCmd {
+            cmd_lb: 0x60,
+            cmd_ub: Some(0x9F),
+            addr: None,
+            addr_width: None,
+            bus_width: NorStorageBusWidth::Octal,
+            mode: NorStorageCmdMode::DDR,
+            dummy: NorStorageDummyCycles::Clocks(0),
+            cmdtype: None,
+            data_bytes: None,
+        };
+        self.storagebusdriver
+            .send_command(chip_erase_cmd, None, None)
+            .map_err(NorErrorType::FlashStorageErrorBus)?;
+
+        self.wait_for_idle()
+    }
+
+    /// Poll the status register until the write-in-progress bit clears
+    pub fn wait_for_operation_completion(&mut self) -> Result<(), NorErrorType> {
+        while self.is_busy()? {}
+
+        Ok(())
+    }
+
+    /// Poll the status register until the write-in-progress bit clears, giving up with
+    /// `FlashStorageErrorTimeout` after `max_iters` polls
+    pub fn wait_for_operation_completion_timeout(&mut self, max_iters: u32) -> Result<(), NorErrorType> {
+        for _ in 0..max_iters {
+            if !self.is_busy()? {
+                return Ok(());
+            }
+        }
+
+        Err(NorErrorType::FlashStorageErrorTimeout)
+    }
+
+    /// Bound the status polling done by erase and write, `None` waits forever
+    pub fn set_wip_poll_limit(&mut self, limit: Option<u32>) {
+        self.wip_poll_limit = limit;
+    }
+
+    fn wait_for_idle(&mut self) -> Result<(), NorErrorType> {
+        match self.wip_poll_limit {
+            Some(max_iters) => self.wait_for_operation_completion_timeout(max_iters),
+            None => self.wait_for_operation_completion(),
+        }
+    }
+
+    fn is_busy(&mut self) -> Result<bool, NorErrorType> {
+        let mut status = [0_u8; 4];
+
+        let read_status_cmd = NorStorageCmd {
+            cmd_lb: 0x05,
+            cmd_ub: Some(0xFA),
+            addr: Some(0),
+            addr_width: Some(0x20),
+            bus_width: NorStorageBusWidth::Octal,
+            mode: NorStorageCmdMode::DDR,
+            dummy: NorStorageDummyCycles::Clocks(0x14),
+            cmdtype: Some(NorStorageCmdType::Read),
+            data_bytes: Some(0x4),
+        };
+        self.storagebusdriver
+            .send_command(read_status_cmd, Some(&mut status), None)
+            .map_err(NorErrorType::FlashStorageErrorBus)?;
+
+        Ok(status[0] & 0x01 != 0)
+    }
+
+    /// Create a driver for a flash of `capacity` bytes laid out as `geometry` behind `storagebusdriver`
+    pub fn new_blocking(storagebusdriver: T, capacity: usize, geometry: FlexspiNorGeometry) -> Self {
+        Self {
+            storagebusdriver,
+            capacity,
+            geometry,
+            wip_poll_limit: None,
+            _phantom: core::marker::PhantomData,
+        }
+    }
+}
@@ -1483,6 +1483,138 @@ impl ClockOutConfig {
     }
 }
 
+/// FlexSPI functional clock sources
+///
+/// The main clock is not offered since it follows the core clock and
+/// changing it underneath an XIP FlexSPI leads to fetch errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlexspiClkSrc {
+    /// Main PLL clock
+    MainPllClk,
+    /// `SysPll` Aux0 clock
+    Aux0PllClk,
+    /// FFRO clock
+    Ffro,
+    /// `SysPll` Aux1 clock
+    Aux1PllClk,
+}
+
+impl From<FlexspiClkSrc> for u8 {
+    fn from(value: FlexspiClkSrc) -> Self {
+        match value {
+            FlexspiClkSrc::MainPllClk => 1,
+            FlexspiClkSrc::Aux0PllClk => 2,
+            FlexspiClkSrc::Ffro => 3,
+            FlexspiClkSrc::Aux1PllClk => 4,
+        }
+    }
+}
+
+impl TryFrom<u8> for FlexspiClkSrc {
+    type Error = ClockError;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(FlexspiClkSrc::MainPllClk),
+            2 => Ok(FlexspiClkSrc::Aux0PllClk),
+            3 => Ok(FlexspiClkSrc::Ffro),
+            4 => Ok(FlexspiClkSrc::Aux1PllClk),
+            _ => Err(ClockError::ClockNotSupported),
+        }
+    }
+}
+
+/// Current FFRO rate, as trimmed in hardware
+fn ffro_rate() -> u32 {
+    // SAFETY: unsafe needed to take pointer to Clkctl0, only used for reads
+    let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+    if clkctl0.ffroctl0().read().trim_range().is_ffro_48mhz() {
+        Into::into(FfroFreq::Ffro48m)
+    } else {
+        Into::into(FfroFreq::Ffro60m)
+    }
+}
+
+/// Current rate of a System PLL output, computed from the PLL registers
+fn syspll_output_rate(src: FlexspiClkSrc) -> Result<u32, ClockError> {
+    // SAFETY: unsafe needed to take pointer to Clkctl0, only used for reads
+    let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+
+    let base_rate: u64 = match clkctl0.syspll0clksel().read().sel().bits() {
+        0 => SFRO_FREQ.into(),
+        1 => SYS_OSC_DEFAULT_FREQ.into(),
+        2 => (ffro_rate() / 2).into(),
+        _ => return Err(ClockError::ClockNotEnabled),
+    };
+    let vco = base_rate * u64::from(clkctl0.syspll0ctl0().read().mult().bits());
+
+    let pfd = clkctl0.syspll0pfd().read();
+    let (pfd, div) = match src {
+        FlexspiClkSrc::MainPllClk => (pfd.pfd0().bits(), clkctl0.mainpllclkdiv().read().div().bits()),
+        FlexspiClkSrc::Aux0PllClk => (pfd.pfd2().bits(), clkctl0.aux0pllclkdiv().read().div().bits()),
+        FlexspiClkSrc::Aux1PllClk => (pfd.pfd3().bits(), clkctl0.aux1pllclkdiv().read().div().bits()),
+        FlexspiClkSrc::Ffro => return Err(ClockError::ClockMismatch),
+    };
+    if pfd == 0 {
+        return Err(ClockError::ClockNotEnabled);
+    }
+
+    // PFD output is vco * 18 / pfd, followed by the integer divider
+    Ok((vco * 18 / u64::from(pfd) / (u64::from(div) + 1)) as u32)
+}
+
+/// Select the FlexSPI functional clock source and divider.
+///
+/// `div` is the actual divide value (1-256). Returns the resulting FlexSPI
+/// root clock rate in Hz, to be passed on as `flexspi_root_clk`.
+///
+/// Must not be called while executing in place from the FlexSPI being
+/// reconfigured.
+pub fn set_flexspi_clock(src: FlexspiClkSrc, div: u16) -> Result<u32, ClockError> {
+    if !(1..=256).contains(&div) {
+        return Err(ClockError::InvalidDiv);
+    }
+
+    let src_rate = match src {
+        FlexspiClkSrc::Ffro => ffro_rate(),
+        _ => syspll_output_rate(src)?,
+    };
+
+    // SAFETY: unsafe needed to take pointer to Clkctl0, needed to modify clock HW
+    let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+
+    // SAFETY: unsafe needed to write the bits, all values returned by
+    // FlexspiClkSrc are valid selections
+    clkctl0
+        .flexspifclksel()
+        .write(|w| unsafe { w.sel().bits(u8::from(src)) });
+
+    // Subtract 1 since 0-> 1, 1-> 2, etc...
+    clkctl0.flexspifclkdiv().modify(|_, w| w.reset().set_bit());
+    // SAFETY: unsafe needed to write the bits for flexspifclkdiv
+    clkctl0
+        .flexspifclkdiv()
+        .write(|w| unsafe { w.div().bits((div - 1) as u8) }.halt().clear_bit());
+    while clkctl0.flexspifclkdiv().read().reqflag().bit_is_set() {}
+
+    Ok(src_rate / u32::from(div))
+}
+
+/// Current FlexSPI root clock rate in Hz, read back from the clock tree.
+pub fn flexspi_clock_rate() -> Result<u32, ClockError> {
+    // SAFETY: unsafe needed to take pointer to Clkctl0, only used for reads
+    let clkctl0 = unsafe { crate::pac::Clkctl0::steal() };
+
+    let src = FlexspiClkSrc::try_from(clkctl0.flexspifclksel().read().sel().bits())?;
+    let src_rate = match src {
+        FlexspiClkSrc::Ffro => ffro_rate(),
+        _ => syspll_output_rate(src)?,
+    };
+    let div = u32::from(clkctl0.flexspifclkdiv().read().div().bits()) + 1;
+
+    Ok(src_rate / div)
+}
+
 /// Using the config, enables all desired clocks to desired clock rates
 fn init_clock_hw(config: ClockConfig) -> Result<(), ClockError> {
     if let Err(e) = config.rtc.enable_and_reset() {
@@ -1586,6 +1718,64 @@ pub fn reset<T: SysconPeripheral>() {
 pub fn disable<T: SysconPeripheral>() {
     T::disable_perph_clock();
 }
+
+/// Trait to expose perph clock rates
+trait SealedClockPeripheral {
+    fn clock_rate() -> Result<u32, ClockError>;
+}
+
+/// Functional clock rate query for peripherals
+#[allow(private_bounds)]
+pub trait ClockPeripheral: SealedClockPeripheral + SysconPeripheral {}
+
+/// Current functional clock rate of peripheral `T` in Hz.
+///
+/// The rate is read back from the clock source and divider registers, so it
+/// follows any change made to the clock tree after init.
+pub fn frequency<T: ClockPeripheral>() -> Result<u32, ClockError> {
+    T::clock_rate()
+}
+
+/// Current Flexcomm functional clock rate in Hz
+fn flexcomm_clock_rate(idx: usize) -> Result<u32, ClockError> {
+    // SAFETY: unsafe needed to take pointer to Clkctl1, only used for reads
+    let clkctl1 = unsafe { crate::pac::Clkctl1::steal() };
+    let fc = clkctl1.flexcomm(idx);
+
+    let fclksel = fc.fcfclksel().read().sel();
+    if fclksel.is_sfro_clk() {
+        Ok(SFRO_FREQ)
+    } else if fclksel.is_ffro_clk() {
+        Ok(ffro_rate())
+    } else if fclksel.is_fcn_frg_clk() {
+        // FRG is always programmed with mult = 0, i.e. a pass-through
+        let frgclksel = fc.frgclksel().read().sel();
+        if frgclksel.is_sfro_clk() {
+            Ok(SFRO_FREQ)
+        } else if frgclksel.is_ffro_clk() {
+            Ok(ffro_rate())
+        } else {
+            Err(ClockError::ClockNotSupported)
+        }
+    } else if fclksel.is_none() {
+        Err(ClockError::ClockNotEnabled)
+    } else {
+        Err(ClockError::ClockNotSupported)
+    }
+}
+
+macro_rules! impl_perph_clk_rate {
+    ($peripheral:ident, $rate:expr) => {
+        impl SealedClockPeripheral for crate::peripherals::$peripheral {
+            fn clock_rate() -> Result<u32, ClockError> {
+                $rate
+            }
+        }
+
+        impl ClockPeripheral for crate::peripherals::$peripheral {}
+    };
+}
+
 macro_rules! impl_perph_clk {
     ($peripheral:ident, $clkctl:ident, $clkreg:ident, $rstctl:ident, $rstreg:ident, $bit:expr) => {
         impl SealedSysconPeripheral for crate::peripherals::$peripheral {
@@ -1688,3 +1878,13 @@ impl_perph_clk!(USDHC1, Clkctl0, pscctl1, Rstctl0, prstctl1, 3);
 impl_perph_clk!(UTICK0, Clkctl0, pscctl2, Rstctl0, prstctl2, 0);
 impl_perph_clk!(WDT0, Clkctl0, pscctl2, Rstctl0, prstctl2, 1);
 impl_perph_clk!(WDT1, Clkctl1, pscctl2, Rstctl1, prstctl2, 10);
+
+impl_perph_clk_rate!(FLEXCOMM0, flexcomm_clock_rate(0));
+impl_perph_clk_rate!(FLEXCOMM1, flexcomm_clock_rate(1));
+impl_perph_clk_rate!(FLEXCOMM2, flexcomm_clock_rate(2));
+impl_perph_clk_rate!(FLEXCOMM3, flexcomm_clock_rate(3));
+impl_perph_clk_rate!(FLEXCOMM4, flexcomm_clock_rate(4));
+impl_perph_clk_rate!(FLEXCOMM5, flexcomm_clock_rate(5));
+impl_perph_clk_rate!(FLEXCOMM6, flexcomm_clock_rate(6));
+impl_perph_clk_rate!(FLEXCOMM7, flexcomm_clock_rate(7));
+impl_perph_clk_rate!(FLEXSPI, flexspi_clock_rate());
@@ -126,16 +126,17 @@ impl<'d> Channel<'d> {
         let channel = self.info.ch_num;
 
         // Configure the channel descriptor
-        // NOTE: the DMA controller expects the memory buffer end address but peripheral address is actual
+        // NOTE: the DMA controller expects the memory buffer end address but peripheral address is actual,
+        // unless the peripheral address increments as well
         // SAFETY: unsafe due to use of a mutable static (DESCRIPTORS.list)
         unsafe {
             DESCRIPTORS.list[channel].reserved = 0;
-            if dir == Direction::MemoryToPeripheral {
+            if dir == Direction::MemoryToPeripheral && !options.peri_addr_increment {
                 DESCRIPTORS.list[channel].dst_data_end_addr = dstbase as u32;
             } else {
                 DESCRIPTORS.list[channel].dst_data_end_addr = dstbase as u32 + (xfercount * xferwidth) as u32;
             }
-            if dir == Direction::PeripheralToMemory {
+            if dir == Direction::PeripheralToMemory && !options.peri_addr_increment {
                 DESCRIPTORS.list[channel].src_data_end_addr = srcbase as u32;
             } else {
                 DESCRIPTORS.list[channel].src_data_end_addr = srcbase as u32 + (xfercount * xferwidth) as u32;
@@ -169,12 +170,12 @@ impl<'d> Channel<'d> {
             w.reload().clear_bit();
             w.setinta().set_bit();
             w.width().bits(options.width.into());
-            if dir == Direction::PeripheralToMemory {
+            if dir == Direction::PeripheralToMemory && !options.peri_addr_increment {
                 w.srcinc().bits(0);
             } else {
                 w.srcinc().bits(1);
             }
-            if dir == Direction::MemoryToPeripheral {
+            if dir == Direction::MemoryToPeripheral && !options.peri_addr_increment {
                 w.dstinc().bits(0);
             } else {
                 w.dstinc().bits(1);
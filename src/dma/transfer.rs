@@ -16,6 +16,10 @@ pub struct TransferOptions {
 
     /// Transfer priority level
     pub priority: Priority,
+
+    /// Step the peripheral address by the transfer width, for peripherals that expose their
+    /// FIFO as a window of data registers
+    pub peri_addr_increment: bool,
 }
 
 impl Default for TransferOptions {
@@ -23,6 +27,7 @@ impl Default for TransferOptions {
         Self {
             width: Width::Bit8,
             priority: Priority::Priority0,
+            peri_addr_increment: false,
         }
     }
 }
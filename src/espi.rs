@@ -22,6 +22,12 @@ use crate::{interrupt, peripherals, Peri, PeripheralType};
 // This controller has 5 different eSPI ports
 const ESPI_PORTS: usize = 5;
 
+// Maximum length of raw OOB = 3 + 5 + 64 + 1
+const OOB_MAX_LEN: u8 = 73;
+
+// BASE_OR_ASZ field of the port ADDR register, bits 1:0
+const PORT_ADDR_BASE_OR_ASZ_MASK: u32 = 0b11;
+
 static ESPI_WAKER: AtomicWaker = AtomicWaker::new();
 
 /// Result type alias
@@ -42,6 +48,9 @@ pub enum Error {
 
     /// Invalid Parameter Error
     InvalidParameter,
+
+    /// Port RAM windows overlap
+    PortOverlap,
 }
 
 /// eSPI Command Length
@@ -178,7 +187,13 @@ pub enum PortConfig {
     },
 
     /// Slave Flash
-    SlaveFlash,
+    SlaveFlash {
+        /// 12-bit Word-aligned offset from RAM base
+        offset: u16,
+
+        /// Length of the flash data buffer.
+        length: Len,
+    },
 
     /// Mem Single
     MemSingle,
@@ -198,13 +213,34 @@ impl Into<Type> for PortConfig {
             PortConfig::MailboxSplit { .. } => Type::MailboxSplit,
             PortConfig::PutPcMem32 => Type::MailboxShared,
             PortConfig::MailboxSplitOOB { .. } => Type::MailboxOobSplit,
-            PortConfig::SlaveFlash => Type::BusMFlashS,
+            PortConfig::SlaveFlash { .. } => Type::BusMFlashS,
             PortConfig::MemSingle => Type::BusMMemS,
             PortConfig::MasterFlash => Type::BusMFlashS,
         }
     }
 }
 
+impl PortConfig {
+    /// Offset from RAM base and size in bytes of the RAM used by this port,
+    /// both directions included.
+    fn ram_window(&self) -> Option<(u32, usize)> {
+        let (offset, length, halves) = match *self {
+            PortConfig::AcpiEndpoint { offset, .. } => (offset, Len::Len4, 1),
+            PortConfig::SlaveFlash { offset, length } => (offset, length, 1),
+            PortConfig::MailboxShared { offset, length, .. } | PortConfig::MailboxSingle { offset, length, .. } => {
+                (offset, length, 1)
+            }
+            PortConfig::MailboxSplit { offset, length, .. } | PortConfig::MailboxSplitOOB { offset, length } => {
+                (offset, length, 2)
+            }
+            _ => return None,
+        };
+
+        let len = (1usize << (<Len as Into<u8>>::into(length) + 2)) * halves;
+        Some((offset as u32, len))
+    }
+}
+
 impl Default for PortConfig {
     fn default() -> Self {
         Self::Unconfigured
@@ -212,7 +248,7 @@ impl Default for PortConfig {
 }
 
 /// eSPI capabilities.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Capabilities {
     /// Mode of operation
     pub mode: Spicap,
@@ -311,6 +347,38 @@ pub struct PortEvent {
     pub direction: bool,
 }
 
+/// Flash Access channel operation
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlashAccess {
+    /// Host reads from flash, data must be placed in the port buffer
+    Read,
+
+    /// Host writes to flash, data is available in the port buffer
+    Write,
+
+    /// Host erases flash starting at the given address
+    Erase,
+}
+
+/// Flash Access channel event data
+pub struct FlashEvent {
+    /// Port that event occurred on
+    pub port: usize,
+
+    /// Base address of data buffer
+    pub base_addr: u32,
+
+    /// Flash address requested by the host
+    pub flash_addr: u32,
+
+    /// Size of access
+    pub length: usize,
+
+    /// Requested operation
+    pub access: FlashAccess,
+}
+
 /// Wire Change Event
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct WireChangeEvent {
@@ -417,6 +485,9 @@ pub enum Event {
     /// Peripheral event on port 0-4
     PeripheralEvent(PortEvent),
 
+    /// Flash Access channel request on port 0-4
+    FlashEvent(FlashEvent),
+
     /// Port 80 has pending events
     Port80,
 
@@ -442,6 +513,83 @@ impl From<BootStatus> for bool {
     }
 }
 
+/// eSPI virtual wires.
+///
+/// Levels are logical: `true` means the wire is asserted, regardless of
+/// the wire being active high or active low on the bus.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum VirtualWire {
+    /// SLP_S3#, Host to EC
+    SlpS3,
+
+    /// SLP_S4#, Host to EC
+    SlpS4,
+
+    /// SLP_S5#, Host to EC
+    SlpS5,
+
+    /// SUS_STAT#, Host to EC
+    SusStat,
+
+    /// PLTRST#, Host to EC
+    PltRst,
+
+    /// OOB_RST_WARN, Host to EC
+    OobRstWarn,
+
+    /// HOST_RST_WARN, Host to EC
+    HostRstWarn,
+
+    /// SUS_WARN#, Host to EC
+    SusWarn,
+
+    /// SUS_PWRDN_ACK, Host to EC
+    SusPwrdnAck,
+
+    /// SLP_A#, Host to EC
+    SlpA,
+
+    /// SLP_LAN#, Host to EC
+    SlpLan,
+
+    /// SLP_WLAN#, Host to EC
+    SlpWlan,
+
+    /// HOST_C10, Host to EC
+    HostC10,
+
+    /// WAKE#, EC to Host
+    Wake,
+
+    /// PME#, EC to Host
+    Pme,
+
+    /// SCI#, EC to Host
+    Sci,
+
+    /// SMI#, EC to Host
+    Smi,
+
+    /// RCIN#, EC to Host
+    Rcin,
+
+    /// HOST_RST_ACK, EC to Host
+    HostRstAck,
+
+    /// SUS_ACK#, EC to Host
+    SusAck,
+
+    /// OOB_RST_ACK, EC to Host
+    OobRstAck,
+
+    /// SLAVE_BOOT_LOAD_DONE, EC to Host
+    BootDone,
+
+    /// DSW_PWROK reset, EC to Host
+    DswPwrokRst,
+}
+
 /// eSPI driver.
 pub struct Espi<'d> {
     info: Info,
@@ -451,6 +599,9 @@ pub struct Espi<'d> {
 
 impl<'d> Espi<'d> {
     /// Instantiates new eSPI peripheral and initializes to default values.
+    ///
+    /// Returns `Error::PortOverlap` if the RAM windows of two configured
+    /// ports overlap.
     pub fn new<T: Instance>(
         _peripheral: Peri<'d, T>,
         _clk: Peri<'d, impl ClkPin<T>>,
@@ -463,7 +614,24 @@ impl<'d> Espi<'d> {
         _alert: Peri<'d, impl AlertPin<T>>,
         _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
         config: Config,
-    ) -> Espi<'d> {
+    ) -> Result<Espi<'d>> {
+        // Reject overlapping port RAM windows before touching the hardware
+        for (i, a) in config.ports_config.iter().enumerate() {
+            let Some((a_off, a_len)) = a.ram_window() else {
+                continue;
+            };
+
+            for b in &config.ports_config[i + 1..] {
+                let Some((b_off, b_len)) = b.ram_window() else {
+                    continue;
+                };
+
+                if a_off < b_off + b_len as u32 && b_off < a_off + a_len as u32 {
+                    return Err(Error::PortOverlap);
+                }
+            }
+        }
+
         _alert.as_alert();
         _rst.as_rst();
         _cs.as_cs();
@@ -556,7 +724,7 @@ impl<'d> Espi<'d> {
         T::Interrupt::unpend();
         unsafe { T::Interrupt::enable() };
 
-        instance
+        Ok(instance)
     }
 
     /// Configure the port to a given mode
@@ -597,6 +765,17 @@ impl<'d> Espi<'d> {
                 self.mailbox(port, config.into(), direction, base_sel, offset, length);
             }
 
+            PortConfig::SlaveFlash { offset, length } => {
+                self.mailbox(
+                    port,
+                    config.into(),
+                    Direction::BidirectionalUnenforced,
+                    BaseOrAsz::OffsetFrom0,
+                    offset,
+                    length,
+                );
+            }
+
             PortConfig::MailboxSplitOOB { offset, length } => {
                 self.mailbox(
                     port,
@@ -634,6 +813,38 @@ impl<'d> Espi<'d> {
         });
     }
 
+    /// Complete Flash Access channel request
+    ///
+    /// For [`FlashAccess::Read`] requests `length` is the number of bytes
+    /// placed in the port buffer, it must be between 1 and the buffer size
+    /// (at most 256). Write and erase requests are completed with `length`
+    /// set to 0.
+    pub async fn complete_flash(&mut self, port: usize, length: usize) -> Result<()> {
+        if !matches!(self.config.ports_config.get(port), Some(PortConfig::SlaveFlash { .. })) {
+            return Err(Error::InvalidPort);
+        }
+
+        if length > 0 {
+            let (_, buf_len) = self.port_window(port)?;
+
+            if length > buf_len.min(256) {
+                return Err(Error::InvalidParameter);
+            }
+
+            // SAFETY: Length range checked above
+            self.info
+                .regs
+                .port(port)
+                .omflen()
+                .write(|w| unsafe { w.len().bits((length - 1) as u8) });
+        }
+
+        self.info.regs.port(port).irulestat().modify(|_, w| w.sstcl().mcudone());
+        self.complete_port(port).await;
+
+        Ok(())
+    }
+
     fn get_port_event(&mut self, port: usize) -> Poll<Result<Event>> {
         // If port is not configured ignore and return Poll::Pending
         if self.config.ports_config[port] == PortConfig::Unconfigured {
@@ -675,6 +886,32 @@ impl<'d> Espi<'d> {
                     direction: direction,
                 })))
             }
+            PortConfig::SlaveFlash { offset, .. } => {
+                let address = self.config.ram_base + offset as u32;
+
+                // Erase requests carry no data and are flagged through the
+                // port-specific status bit INTSPC0 (UM11147, eSPI chapter,
+                // Port n STAT register, flash port type).
+                let access = if self.info.regs.port(port).stat().read().intspc0().bit_is_set() {
+                    FlashAccess::Erase
+                } else if direction {
+                    FlashAccess::Write
+                } else {
+                    FlashAccess::Read
+                };
+
+                // The low bits of ADDR hold BASE_OR_ASZ, the address latched
+                // from the request sits above them
+                let flash_addr = self.info.regs.port(port).addr().read().bits() & !PORT_ADDR_BASE_OR_ASZ_MASK;
+
+                Poll::Ready(Ok(Event::FlashEvent(FlashEvent {
+                    port: port,
+                    base_addr: address,
+                    flash_addr: flash_addr,
+                    length: length,
+                    access: access,
+                })))
+            }
             _ => {
                 return Poll::Pending;
             }
@@ -700,24 +937,7 @@ impl<'d> Espi<'d> {
                 } else if me.info.regs.mstat().read().wire_chg().bit_is_set() {
                     me.info.regs.mstat().write(|w| w.wire_chg().clear_bit_by_one());
 
-                    let wirero = me.info.regs.wirero().read();
-
-                    let event = WireChangeEvent {
-                        slp_s3n: wirero.slp_s3n().bit_is_set(),
-                        slp_s4n: wirero.slp_s4n().bit_is_set(),
-                        slp_s5n: wirero.slp_s5n().bit_is_set(),
-                        sus_stat: wirero.sus_stat().bit_is_set(),
-                        pltrstn: wirero.pltrstn().bit_is_set(),
-                        oob_rst_warn: wirero.oob_rst_warn().bit_is_set(),
-                        host_rst_warn: wirero.host_rst_warn().bit_is_set(),
-                        sus_warnn: wirero.sus_warnn().bit_is_set(),
-                        sus_pwrdn_ackn: wirero.sus_pwrdn_ackn().bit_is_set(),
-                        slp_an: wirero.slp_an().bit_is_set(),
-                        slp_lann: wirero.slp_lann().bit_is_set(),
-                        slp_wlann: wirero.slp_wlann().bit_is_set(),
-                        p2e: wirero.p2e().bits(),
-                        host_c10n: wirero.host_c10n().bit_is_set(),
-                    };
+                    let event = me.wire_status();
 
                     Poll::Ready(Ok(Event::WireChange(event)))
                 } else if me.info.regs.mstat().read().crcerr().bit_is_set() {
@@ -849,13 +1069,83 @@ impl<'d> Espi<'d> {
         }
     }
 
+    /// Copy the OOB message received from the host into `buf`
+    ///
+    /// `event` is the [`Event::OOBEvent`] with direction: false reporting the
+    /// message, its length is the number of bytes received. Returns that
+    /// length. Complete the port with [`Espi::complete_port`] once done with the
+    /// message.
+    ///
+    /// SAFETY: Same requirements as [`Espi::port_buffer`].
+    pub unsafe fn oob_read_data(&self, event: &PortEvent, buf: &mut [u8]) -> Result<usize> {
+        if !matches!(
+            self.config.ports_config.get(event.port),
+            Some(PortConfig::MailboxSplitOOB { .. })
+        ) {
+            return Err(Error::InvalidPort);
+        }
+
+        // The host-to-device half comes first
+        let (addr, len) = self.port_window(event.port)?;
+        if event.length > len / 2 || event.length > buf.len() {
+            return Err(Error::InvalidParameter);
+        }
+
+        let message = slice::from_raw_parts(addr as *const u8, event.length);
+        buf[..event.length].copy_from_slice(message);
+
+        Ok(event.length)
+    }
+
+    /// Copy `data` into the OOB write buffer and send it to the host
+    ///
+    /// Combines [`Espi::oob_get_write_buffer`] and [`Espi::oob_write_data`],
+    /// `data` must be between 1 and 73 bytes long.
+    ///
+    /// SAFETY: Same requirements as [`Espi::oob_get_write_buffer`].
+    pub unsafe fn oob_send_data(&mut self, port: usize, data: &[u8]) -> Result<()> {
+        let length = u8::try_from(data.len()).map_err(|_| Error::InvalidParameter)?;
+        if !(1..=OOB_MAX_LEN).contains(&length) {
+            return Err(Error::InvalidParameter);
+        }
+
+        let buf = self.oob_get_write_buffer(port)?;
+        buf.get_mut(..data.len())
+            .ok_or(Error::InvalidParameter)?
+            .copy_from_slice(data);
+
+        self.oob_write_data(port, length)
+    }
+
+    /// Return the RAM window backing a configured port
+    ///
+    /// Split ports return both halves, the host-to-device half first.
+    ///
+    /// SAFETY: Port config must point to a valid memory region that has been
+    /// carved out in memory.x. The host may write to this region at any time,
+    /// so contents are only stable while handling an event for this port.
+    pub unsafe fn port_buffer(&self, port: usize) -> Result<&[u8]> {
+        let (addr, len) = self.port_window(port)?;
+        Ok(slice::from_raw_parts(addr as *const u8, len))
+    }
+
+    /// Return the mutable RAM window backing a configured port
+    ///
+    /// Split ports return both halves, the host-to-device half first.
+    ///
+    /// SAFETY: Same requirements as [`Espi::port_buffer`]. Additionally no
+    /// other reference to the same window may be alive.
+    pub unsafe fn port_buffer_mut(&mut self, port: usize) -> Result<&mut [u8]> {
+        let (addr, len) = self.port_window(port)?;
+        Ok(slice::from_raw_parts_mut(addr as *mut u8, len))
+    }
+
     /// Write OOB data from device to host in OOB write buffer
     /// This starts a transfer, upon completion INTWR event on OOB port is triggered
     ///
     /// Length must be between 1 and 73
     pub fn oob_write_data(&mut self, port: usize, length: u8) -> Result<()> {
-        // Maximum length of raw OOB = 3 + 5 + 64 + 1
-        if (1..73).contains(&length) {
+        if (1..=OOB_MAX_LEN).contains(&length) {
             // SAFETY: Valid length range 1-73 checked previous
             self.info
                 .regs
@@ -1002,6 +1292,132 @@ impl<'d> Espi<'d> {
         self.block_for_vwire_done();
     }
 
+    /// Current level of a virtual wire.
+    ///
+    /// Host to EC wires report the level last received from the Host,
+    /// EC to Host wires report the level last driven by the EC.
+    pub fn get_virtual_wire(&self, wire: VirtualWire) -> bool {
+        let ro = self.info.regs.wirero().read();
+        let wo = self.info.regs.wirewo().read();
+
+        match wire {
+            VirtualWire::SlpS3 => ro.slp_s3n().bit_is_clear(),
+            VirtualWire::SlpS4 => ro.slp_s4n().bit_is_clear(),
+            VirtualWire::SlpS5 => ro.slp_s5n().bit_is_clear(),
+            VirtualWire::SusStat => ro.sus_stat().bit_is_clear(),
+            VirtualWire::PltRst => ro.pltrstn().bit_is_clear(),
+            VirtualWire::OobRstWarn => ro.oob_rst_warn().bit_is_set(),
+            VirtualWire::HostRstWarn => ro.host_rst_warn().bit_is_set(),
+            VirtualWire::SusWarn => ro.sus_warnn().bit_is_clear(),
+            VirtualWire::SusPwrdnAck => ro.sus_pwrdn_ackn().bit_is_clear(),
+            VirtualWire::SlpA => ro.slp_an().bit_is_clear(),
+            VirtualWire::SlpLan => ro.slp_lann().bit_is_clear(),
+            VirtualWire::SlpWlan => ro.slp_wlann().bit_is_clear(),
+            VirtualWire::HostC10 => ro.host_c10n().bit_is_clear(),
+            VirtualWire::Wake => wo.waken_scin().bit_is_clear(),
+            VirtualWire::Pme => wo.pmen().bit_is_clear(),
+            VirtualWire::Sci => wo.scin().bit_is_clear(),
+            VirtualWire::Smi => wo.smin().bit_is_clear(),
+            VirtualWire::Rcin => wo.rcinn().bit_is_clear(),
+            VirtualWire::HostRstAck => wo.host_rst_ack().bit_is_set(),
+            VirtualWire::SusAck => wo.susackn().bit_is_clear(),
+            VirtualWire::OobRstAck => wo.oob_rst_ack().bit_is_set(),
+            VirtualWire::BootDone => wo.boot_done().bit_is_set(),
+            VirtualWire::DswPwrokRst => wo.dsw_pwrok_rst().bit_is_set(),
+        }
+    }
+
+    /// Drive an EC to Host virtual wire.
+    ///
+    /// Returns `Error::InvalidParameter` for Host to EC wires.
+    ///
+    /// Warning: Blocks until DONE bit clears
+    pub fn set_virtual_wire(&mut self, wire: VirtualWire, level: bool) -> Result<()> {
+        match wire {
+            VirtualWire::Wake => self.wake(level),
+            VirtualWire::Pme => self.pme(level),
+            VirtualWire::Sci => self.sci(level),
+            VirtualWire::Smi => self.smi(level),
+            VirtualWire::Rcin => self.rcin(level),
+            VirtualWire::HostRstAck => {
+                self.info.regs.wirewo().write(|w| w.host_rst_ack().bit(level));
+                self.block_for_vwire_done();
+            }
+            VirtualWire::SusAck => {
+                self.info.regs.wirewo().write(|w| w.susackn().bit(!level));
+                self.block_for_vwire_done();
+            }
+            VirtualWire::OobRstAck => {
+                self.info.regs.wirewo().write(|w| w.oob_rst_ack().bit(level));
+                self.block_for_vwire_done();
+            }
+            VirtualWire::BootDone => {
+                self.info.regs.wirewo().write(|w| w.boot_done().bit(level));
+                self.block_for_vwire_done();
+            }
+            VirtualWire::DswPwrokRst => {
+                self.info.regs.wirewo().write(|w| w.dsw_pwrok_rst().bit(level));
+                self.block_for_vwire_done();
+            }
+            _ => return Err(Error::InvalidParameter),
+        }
+
+        Ok(())
+    }
+
+    /// Snapshot of the virtual wires currently driven by the Host.
+    ///
+    /// Unlike `Event::WireChange` this does not wait for or consume a
+    /// wire change, so it can be polled periodically.
+    pub fn wire_status(&self) -> WireChangeEvent {
+        let wirero = self.info.regs.wirero().read();
+
+        WireChangeEvent {
+            slp_s3n: wirero.slp_s3n().bit_is_set(),
+            slp_s4n: wirero.slp_s4n().bit_is_set(),
+            slp_s5n: wirero.slp_s5n().bit_is_set(),
+            sus_stat: wirero.sus_stat().bit_is_set(),
+            pltrstn: wirero.pltrstn().bit_is_set(),
+            oob_rst_warn: wirero.oob_rst_warn().bit_is_set(),
+            host_rst_warn: wirero.host_rst_warn().bit_is_set(),
+            sus_warnn: wirero.sus_warnn().bit_is_set(),
+            sus_pwrdn_ackn: wirero.sus_pwrdn_ackn().bit_is_set(),
+            slp_an: wirero.slp_an().bit_is_set(),
+            slp_lann: wirero.slp_lann().bit_is_set(),
+            slp_wlann: wirero.slp_wlann().bit_is_set(),
+            p2e: wirero.p2e().bits(),
+            host_c10n: wirero.host_c10n().bit_is_set(),
+        }
+    }
+
+    /// Capabilities advertised to the Host through the general
+    /// capabilities register, as passed in [`Config::caps`].
+    ///
+    /// The Host may select a lower speed or payload size with its
+    /// SET_CONFIGURATION. The controller handles that cycle itself and keeps
+    /// no register with the Host's choice, so it cannot be read back or
+    /// checked against this value.
+    pub fn capabilities(&self) -> Capabilities {
+        self.config.caps
+    }
+
+    /// Returns `true` while the eSPI bus is held in reset by the Host.
+    pub fn in_reset(&self) -> bool {
+        self.info.regs.mstat().read().in_rst().bit_is_set()
+    }
+
+    /// Returns `true` when the Host link is up: the bus is out of reset,
+    /// PLTRST# is deasserted and SLP_S5# is deasserted (both wires are
+    /// active low).
+    ///
+    /// Intended to be polled for supervision, e.g. to detect the Host
+    /// going away after a crash or shutdown.
+    pub fn host_alive(&self) -> bool {
+        let wires = self.wire_status();
+
+        !self.in_reset() && wires.pltrstn && wires.slp_s5n
+    }
+
     fn block_for_vwire_done(&self) {
         // No interrupt event available, must busy loop
         while self.info.regs.wirewo().read().done().bit_is_clear() {}
@@ -1031,6 +1447,17 @@ impl<'d> Espi<'d> {
 }
 
 impl Espi<'_> {
+    fn port_window(&self, port: usize) -> Result<(u32, usize)> {
+        let (offset, len) = self
+            .config
+            .ports_config
+            .get(port)
+            .and_then(PortConfig::ram_window)
+            .ok_or(Error::InvalidPort)?;
+
+        Ok((self.config.ram_base + offset, len))
+    }
+
     fn mailbox(
         &mut self,
         port: usize,
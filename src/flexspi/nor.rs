@@ -1,11 +1,17 @@
 //! FlexSPI NOR Storage Bus Driver module for the NXP RT6xx family of microcontrollers
 //!
 use core::cmp::min;
+use core::future::poll_fn;
+use core::marker::PhantomData;
+use core::sync::atomic::{AtomicU8, Ordering};
+use core::task::Poll;
 
+use embassy_hal_internal::drop::OnDrop;
 use embassy_hal_internal::{Peri, PeripheralType};
+use embassy_sync::waitqueue::AtomicWaker;
 #[cfg(feature = "time")]
-use embassy_time::Instant;
-use mimxrt600_fcb::FlexSpiLutOpcode;
+use embassy_time::{Duration, Instant, Timer};
+pub use mimxrt600_fcb::FlexSpiLutOpcode;
 use mimxrt600_fcb::FlexSpiLutOpcode::*;
 use paste::paste;
 use storage_bus::nor::{
@@ -13,9 +19,10 @@ use storage_bus::nor::{
     NorStorageCmdType, NorStorageDummyCycles,
 };
 
-use crate::clocks::enable_and_reset;
+use crate::clocks::{delay_loop_clocks, enable_and_reset};
 #[cfg(feature = "time")]
 use crate::flexspi::is_expired;
+use crate::interrupt::typelevel::Interrupt;
 use crate::iopctl::IopctlPin as Pin;
 use crate::pac::flexspi::ahbcr::*;
 use crate::pac::flexspi::flshcr1::*;
@@ -23,10 +30,10 @@ use crate::pac::flexspi::flshcr2::*;
 use crate::pac::flexspi::flshcr4::*;
 use crate::pac::flexspi::mcr0::*;
 use crate::pac::flexspi::mcr2::*;
-use crate::{interrupt, peripherals};
+use crate::{dma, interrupt};
 
 macro_rules! configure_ports_a {
-    ($port:expr, $regs: ident, $device_config: ident, $flash_size: ident) => {
+    ($port:expr, $regs: ident, $device_config: ident, $flash_size: ident, $read_only: expr) => {
         paste! {
             $regs.[<flsha $port cr0>]().modify(|_, w| unsafe { w.flshsz().bits($flash_size) });
             $regs.[<flshcr1a $port>]().modify(|_, w| unsafe {
@@ -43,8 +50,23 @@ macro_rules! configure_ports_a {
                     .csintervalunit()
                     .variant($device_config.cs_interval_unit)
             });
-            $regs.[<flshcr2a $port>]()
-                .modify(|_, w| w.awrwaitunit().variant($device_config.ahb_write_wait_unit));
+            $regs.[<flshcr2a $port>]().modify(|_, w| unsafe {
+                w.awrwaitunit()
+                    .variant($device_config.ahb_write_wait_unit)
+                    .awrwait()
+                    .bits($device_config.ahb_write_wait_interval)
+            });
+
+            // A read-only bus never gets an AHB write sequence, so stores to the window are not
+            // turned into flash commands
+            if !$read_only && $device_config.awr_seq_number > 0 {
+                $regs.[<flshcr2a $port>]().modify(|_, w| unsafe {
+                    w.awrseqnum()
+                        .bits($device_config.awr_seq_number - 1)
+                        .awrseqid()
+                        .bits($device_config.awr_seq_index)
+                });
+            }
 
             if $device_config.ard_seq_number > 0 {
                 $regs.[<flshcr2a $port>]().modify(|_, w| unsafe {
@@ -58,8 +80,28 @@ macro_rules! configure_ports_a {
     };
 }
 
+macro_rules! configure_ahb_rx_buffers {
+    ($regs: ident, $buffers: expr, $($idx:literal),+) => {
+        paste! {
+            $(
+                // Buffer sizes are given in bytes, the register counts 64 bit units
+                $regs.[<ahbrxbuf $idx cr0>]().modify(|_, w| unsafe {
+                    w.mstrid()
+                        .bits($buffers[$idx].master_index)
+                        .prefetchen()
+                        .bit($buffers[$idx].enable_prefetch)
+                        .bufsz()
+                        .bits($buffers[$idx].buffer_size / 8)
+                        .priority()
+                        .bits($buffers[$idx].priority)
+                });
+            )+
+        }
+    };
+}
+
 macro_rules! configure_ports_b {
-    ($port:expr, $regs: ident, $device_config: ident, $flash_size: ident) => {
+    ($port:expr, $regs: ident, $device_config: ident, $flash_size: ident, $read_only: expr) => {
         paste! {
             $regs.[<flshb $port cr0>]().modify(|_, w| unsafe { w.flshsz().bits($flash_size) });
             $regs.[<flshcr1b $port>]().modify(|_, w| unsafe {
@@ -76,8 +118,23 @@ macro_rules! configure_ports_b {
                     .csintervalunit()
                     .variant($device_config.cs_interval_unit)
             });
-            $regs.[<flshcr2b $port>]()
-                .modify(|_, w| w.awrwaitunit().variant($device_config.ahb_write_wait_unit));
+            $regs.[<flshcr2b $port>]().modify(|_, w| unsafe {
+                w.awrwaitunit()
+                    .variant($device_config.ahb_write_wait_unit)
+                    .awrwait()
+                    .bits($device_config.ahb_write_wait_interval)
+            });
+
+            // A read-only bus never gets an AHB write sequence, so stores to the window are not
+            // turned into flash commands
+            if !$read_only && $device_config.awr_seq_number > 0 {
+                $regs.[<flshcr2b $port>]().modify(|_, w| unsafe {
+                    w.awrseqnum()
+                        .bits($device_config.awr_seq_number - 1)
+                        .awrseqid()
+                        .bits($device_config.awr_seq_index)
+                });
+            }
 
             if $device_config.ard_seq_number > 0 {
                 $regs.[<flshcr2b $port>]().modify(|_, w| unsafe {
@@ -94,6 +151,7 @@ macro_rules! configure_ports_b {
 const FIFO_SLOT_SIZE: u32 = 4; // 4 bytes
 const MAX_TRANSFER_SIZE: u32 = 128;
 const OPERATION_SEQ_NUMBER: u8 = 0;
+const LUT_SEQ_COUNT: u8 = 16;
 const LUT_UNLOCK_CODE: u32 = 0x5AF05AF0;
 
 #[cfg(feature = "time")]
@@ -102,15 +160,43 @@ const CMD_COMPLETION_TIMEOUT: u64 = 10; // 10 millisecond
 const DATA_FILL_TIMEOUT: u64 = 10; // 10 millisecond
 #[cfg(feature = "time")]
 const TX_FIFO_FREE_WATERMARK_TIMEOUT: u64 = 10; // 10 millisecond
-#[cfg(feature = "time")]
 const RESET_TIMEOUT: u64 = 10; // 10 millisecond
-#[cfg(feature = "time")]
 const IDLE_TIMEOUT: u64 = 10; // 10 millisecond
+const DLL_LOCK_TIMEOUT: u64 = 10; // 10 millisecond
+const DLL_LOCK_SETTLE_US: u64 = 1; // ERR011377 wants 100 NOPs after DLL lock, 1/3 microsecond at 300MHz
+
+// Fastest core clock of the part, so busy-wait delays derived from it are never too short
+const CORE_CLOCK_MAX_HZ: u64 = 300_000_000;
+
+const FLEXSPI_AHB_BASE: u32 = 0x0800_0000;
+const FLEXSPI_AHB_SECURE_BASE: u32 = 0x1800_0000;
+
+// Word aligned staging buffer for DMA copies to and from the IP FIFO data registers
+#[repr(C, align(4))]
+struct FifoBuffer([u8; MAX_TRANSFER_SIZE as usize]);
+
+// Opcodes (first command byte) of the commands that modify the flash contents or the
+// device configuration: (volatile) write enable, single and quad page program (3 and 4-byte
+// address), erase, OTP program and erase, and status/configuration register writes
+const MODIFYING_OPCODES: [u8; 23] = [
+    0x06, 0x50, 0x02, 0x12, 0x32, 0x38, 0x3E, 0x34, 0x20, 0x21, 0x52, 0x5C, 0xD8, 0xDC, 0x60, 0xC7, 0x42, 0x44, 0x01,
+    0x31, 0x11, 0x72, 0xB1,
+];
 
 const CLOCK_100MHZ: u32 = 100_000_000;
 const DELAYCELLUNIT: u32 = 75; // 75ps
-
-#[derive(Clone, Copy, Debug)]
+const DLL_OVRDVAL_MAX: u32 = 0x3F; // 6 bit OVRDVAL field
+const DLLCR_UNIFIED_CONFIG: u32 = 0x100; // OVRDEN with a single fixed delay cell
+const FLSHSZ_MAX_KB: u32 = 0x7F_FFFF; // 23 bit FLSHSZ field, in KB
+const SFDP_READ_OPCODE: u8 = 0x5A;
+const SFDP_DUMMY_CLOCKS: u8 = 8;
+const ERASE_SUSPEND_OPCODE: u8 = 0x75;
+const ERASE_RESUME_OPCODE: u8 = 0x7A;
+const ENTER_4BYTE_ADDR_OPCODE: u8 = 0xB7;
+const EXIT_4BYTE_ADDR_OPCODE: u8 = 0xE9;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI Port Enum.
 pub enum FlexSpiFlashPort {
     /// FlexSPI Port A
@@ -119,7 +205,27 @@ pub enum FlexSpiFlashPort {
     PortB,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// Alias the FlexSPI AHB window is accessed through
+pub enum FlexspiAhbAlias {
+    /// Non-secure alias at 0x0800_0000
+    NonSecure,
+    /// Secure alias at 0x1800_0000
+    Secure,
+}
+
+impl FlexspiAhbAlias {
+    const fn base(self) -> u32 {
+        match self {
+            Self::NonSecure => FLEXSPI_AHB_BASE,
+            Self::Secure => FLEXSPI_AHB_SECURE_BASE,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI Flash Port Device Instance Enum.
 pub enum FlexSpiFlashPortDeviceInstance {
     /// Device Instance 0
@@ -128,19 +234,30 @@ pub enum FlexSpiFlashPortDeviceInstance {
     DeviceInstance1,
 }
 
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI Configuration Port data structure
 pub struct FlexspiConfigPortData {
     /// FlexSPI Port - PortA or PortB
+    ///
+    /// Only used by the constructors without pins, the others take the port of their pins.
     pub port: FlexSpiFlashPort,
     /// FlexSPI Flash Port Device Instance - DeviceInstance0 or DeviceInstance1
+    ///
+    /// Only used by the constructors without pins, the others take the device instance of
+    /// their chip select.
     pub dev_instance: FlexSpiFlashPortDeviceInstance,
-    /// RX watermark level
+    /// RX watermark level in bytes, a multiple of 8 up to 128
     pub rx_watermark: u8,
-    /// TX watermark level
+    /// TX watermark level in bytes, a multiple of 8 up to 128
     pub tx_watermark: u8,
+    /// Reject every command that could modify the flash contents and leave the AHB write
+    /// sequence unprogrammed
+    pub read_only: bool,
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI Bus Width Enum.
 pub enum FlexSpiBusWidth {
     /// Single bit bus width
@@ -154,6 +271,7 @@ pub enum FlexSpiBusWidth {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI Chip Select Interval unit Enum.
 pub enum FlexspiCsIntervalCycleUnit {
     /// CS interval unit is 1 cycle
@@ -162,6 +280,7 @@ pub enum FlexspiCsIntervalCycleUnit {
     Cycle256,
 }
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI AHB Write Wait unit Enum.
 pub enum FlexspiAhbWriteWaitUnit {
     /// AWRWAIT unit is 2 ahb clock cycle
@@ -183,19 +302,39 @@ pub enum FlexspiAhbWriteWaitUnit {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI Read Sample Clock Enum.
 pub enum FlexspiReadSampleClock {
-    /// Dummy Read strobe generated by FlexSPI self.flexspi_ref and loopback internally
+    /// Dummy Read strobe generated by the FlexSPI controller and loopback internally
     LoopbackInternally,
-    /// Dummy Read strobe generated by FlexSPI self.flexspi_ref and loopback from DQS pad
+    /// Dummy Read strobe generated by the FlexSPI controller and loopback from DQS pad
     LoopbackFromDqsPad,
-    /// SCK output clock and loopback from SCK pad
-    LoopbackFromSckPad,
     /// Flash provided Read strobe and input from DQS pad
     ExternalInputFromDqsPad,
 }
 
+impl From<FlexspiReadSampleClock> for Rxclksrc {
+    fn from(clock: FlexspiReadSampleClock) -> Self {
+        match clock {
+            FlexspiReadSampleClock::LoopbackInternally => Rxclksrc::Rxclksrc0,
+            FlexspiReadSampleClock::LoopbackFromDqsPad => Rxclksrc::Rxclksrc1,
+            FlexspiReadSampleClock::ExternalInputFromDqsPad => Rxclksrc::Rxclksrc3,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// FlexSPI serial clock speed used for a single command
+pub enum FlexspiAccessSpeed {
+    /// Serial clock runs at the configured root clock
+    Full,
+    /// Serial clock runs at half the configured root clock (MCR0.HSEN)
+    Half,
+}
+
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI AHB Buffer Configuration structure
 pub struct FlexspiAhbBufferConfig {
     /// This priority for AHB Master Read which this AHB RX Buffer is assigned.
@@ -209,14 +348,28 @@ pub struct FlexspiAhbBufferConfig {
     pub enable_prefetch: bool,
 }
 
+impl Default for FlexspiAhbBufferConfig {
+    /// 256 byte prefetching buffer for master 0
+    fn default() -> Self {
+        Self {
+            priority: 0,
+            master_index: 0,
+            buffer_size: 256,
+            enable_prefetch: true,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// Flash Device configuration
 pub struct FlexspiDeviceConfig {
     /// FLEXSPI serial root clock
     pub flexspi_root_clk: u32,
     /// FLEXSPI use SCK2
     pub is_sck2_enabled: bool,
-    /// Flash size in KByte
+    /// Flash size in KByte, programmed as is into the FLSHSZ field (also in KByte), e.g.
+    /// `0x10000` for a 64 MByte device. Must fit the 23 bit field.
     pub flash_size_kb: u32,
     /// CS interval unit, 1 or 256 cycle
     pub cs_interval_unit: Csintervalunit,
@@ -251,6 +404,7 @@ pub struct FlexspiDeviceConfig {
 }
 
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// AHB configuration structure
 pub struct AhbConfig {
     /// Enable AHB bus write access to IP TX FIFO.
@@ -280,16 +434,34 @@ pub struct AhbConfig {
     pub enable_ahb_cachable: Cachableen,
 }
 
+impl Default for AhbConfig {
+    /// Prefetching, cachable and bufferable AHB accesses with the longest timeouts
+    fn default() -> Self {
+        Self {
+            enable_ahb_write_ip_rx_fifo: false,
+            enable_ahb_write_ip_tx_fifo: false,
+            ahb_grant_timeout_cycle: 0xff,
+            ahb_bus_timeout_cycle: 0xffff,
+            resume_wait_cycle: 0x20,
+            buffer: [FlexspiAhbBufferConfig::default(); 8],
+            enable_clear_ahb_buffer_opt: Clrahbbufopt::Clrahbbufopt0,
+            enable_read_address_opt: Readaddropt::Readaddropt1,
+            enable_ahb_prefetch: true,
+            enable_ahb_bufferable: Bufferableen::Bufferableen1,
+            enable_ahb_cachable: Cachableen::Cachableen1,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 /// FlexSPI configuration structure
 pub struct FlexspiConfig {
-    /// Sample Clock source selection for Flash Reading.
+    /// Sample Clock source selection for Flash Reading, e.g.
+    /// `FlexspiReadSampleClock::ExternalInputFromDqsPad.into()` for a DDR part driving DQS.
     pub rx_sample_clock: Rxclksrc,
     /// Enable/disable SCK output free-running.
     pub enable_sck_free_running: Sckfreerunen,
-    /// Enable/disable combining PORT A and B Data Pins (SIOA[3:0] and SIOB[3:0]) to support
-    /// Flash Octal mode.
-    pub enable_combination: bool,
     /// Enable/disable doze mode support.
     pub enable_doze: Dozeen,
     /// Enable/disable divide by 2 of the clock for half speed commands.
@@ -309,6 +481,93 @@ pub struct FlexspiConfig {
     pub ahb_config: AhbConfig,
 }
 
+impl Default for FlexspiConfig {
+    /// Settings used with the octal DDR NOR on the EVK: internal loopback sampling, full speed
+    /// serial clock and the longest timeouts. Override single fields with struct update syntax,
+    /// e.g. `FlexspiConfig { enable_doze: Dozeen::Dozeen1, ..Default::default() }`.
+    fn default() -> Self {
+        Self {
+            rx_sample_clock: Rxclksrc::Rxclksrc0,
+            enable_sck_free_running: Sckfreerunen::Sckfreerunen0,
+            enable_doze: Dozeen::Dozeen0,
+            enable_half_speed_access: Hsen::Hsen0,
+            enable_sck_b_diff_opt: Sckbdiffopt::Sckbdiffopt0,
+            enable_same_config_for_all: Samedeviceen::Samedeviceen0,
+            seq_timeout_cycle: 0xFFFF,
+            ip_grant_timeout_cycle: 0xff,
+            ahb_config: AhbConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+/// NOR flash device geometry
+pub struct FlexspiNorGeometry {
+    /// Program page size in bytes
+    pub page_size: u32,
+    /// Sector size in bytes. This is the smallest erasable unit.
+    pub sector_size: u32,
+    /// Block size in bytes
+    pub block_size: u32,
+}
+
+impl FlexspiNorGeometry {
+    /// Index of the sector containing `addr`
+    pub const fn sector_of(&self, addr: u32) -> u32 {
+        addr / self.sector_size
+    }
+
+    /// Index of the block containing `addr`
+    pub const fn block_of(&self, addr: u32) -> u32 {
+        addr / self.block_size
+    }
+
+    /// Index of the page containing `addr`
+    pub const fn page_of(&self, addr: u32) -> u32 {
+        addr / self.page_size
+    }
+
+    /// Start address of the sector with the given index
+    pub const fn sector_base(&self, index: u32) -> u32 {
+        index * self.sector_size
+    }
+
+    /// Start address of the block with the given index
+    pub const fn block_base(&self, index: u32) -> u32 {
+        index * self.block_size
+    }
+
+    /// Number of bytes from `addr` up to the end of its program page.
+    ///
+    /// Page program wraps around inside the page, so a single program command must not
+    /// carry more than this many bytes.
+    pub const fn page_remaining(&self, addr: u32) -> u32 {
+        self.page_size - addr % self.page_size
+    }
+}
+
+/// Types that can be built from any bit pattern read out of flash
+///
+/// # Safety
+///
+/// Implementors must be `Copy`, have no padding and be valid for every possible bit pattern.
+pub unsafe trait FromBytes: Copy {}
+
+macro_rules! impl_from_bytes {
+    ($($t:ty),*) => {
+        $(
+            // SAFETY: primitive integers are valid for any bit pattern
+            unsafe impl FromBytes for $t {}
+        )*
+    };
+}
+
+impl_from_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+// SAFETY: an array of FromBytes values has no padding and is valid for any bit pattern
+unsafe impl<T: FromBytes, const N: usize> FromBytes for [T; N] {}
+
 mod sealed {
     /// simply seal a trait
     pub trait Sealed {}
@@ -318,6 +577,29 @@ impl<T> sealed::Sealed for T {}
 
 struct Info {
     regs: &'static crate::pac::flexspi::RegisterBlock,
+    state: &'static State,
+    enable_and_reset: fn(),
+}
+
+// AHB error flags latched by the interrupt handler
+const AHB_ERR_CMD: u8 = 1 << 0;
+const AHB_ERR_GRANT: u8 = 1 << 1;
+const AHB_ERR_BUS_TIMEOUT: u8 = 1 << 2;
+
+struct State {
+    waker: AtomicWaker,
+    ahb_waker: AtomicWaker,
+    ahb_errors: AtomicU8,
+}
+
+impl State {
+    const fn new() -> Self {
+        Self {
+            waker: AtomicWaker::new(),
+            ahb_waker: AtomicWaker::new(),
+            ahb_errors: AtomicU8::new(0),
+        }
+    }
 }
 
 trait SealedInstance {
@@ -330,18 +612,107 @@ pub trait Instance: SealedInstance + PeripheralType + 'static + Send {
     type Interrupt: interrupt::typelevel::Interrupt;
 }
 
-impl SealedInstance for crate::peripherals::FLEXSPI {
-    fn info() -> Info {
-        Info {
-            // SAFETY: We are just saving the reference of the FlexSPI peripheral address
-            regs: unsafe { &*crate::pac::Flexspi::ptr() },
+macro_rules! impl_instance {
+    ($($peri:ident => $pac:ident),*) => {
+        $(
+            impl SealedInstance for crate::peripherals::$peri {
+                fn info() -> Info {
+                    static STATE: State = State::new();
+
+                    Info {
+                        // SAFETY: We are just saving the reference of the FlexSPI peripheral address
+                        regs: unsafe { &*crate::pac::$pac::ptr() },
+                        state: &STATE,
+                        enable_and_reset: enable_and_reset::<crate::peripherals::$peri>,
+                    }
+                }
+            }
+
+            impl Instance for crate::peripherals::$peri {
+                type Interrupt = crate::interrupt::typelevel::$peri;
+            }
+        )*
+    };
+}
+
+impl_instance!(FLEXSPI => Flexspi);
+
+/// DMA channel serving the FlexSPI IP RX FIFO request
+#[allow(private_bounds)]
+pub trait RxDma<T: Instance>: dma::Instance {}
+
+/// DMA channel serving the FlexSPI IP TX FIFO request
+#[allow(private_bounds)]
+pub trait TxDma<T: Instance>: dma::Instance {}
+
+// The FlexSPI DMA requests are hardwired to these DMA0 channels
+impl RxDma<crate::peripherals::FLEXSPI> for crate::peripherals::DMA0_CH28 {}
+impl TxDma<crate::peripherals::FLEXSPI> for crate::peripherals::DMA0_CH29 {}
+
+/// FlexSPI interrupt handler.
+pub struct InterruptHandler<T: Instance> {
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandler<T> {
+    unsafe fn on_interrupt() {
+        let info = T::info();
+        let regs = info.regs;
+        let intr = regs.intr().read();
+
+        if intr.ipcmddone().bit_is_set()
+            || intr.ipcmderr().bit_is_set()
+            || intr.ipcmdge().bit_is_set()
+            || intr.seqtimeout().bit_is_set()
+        {
+            // Status bits are left pending, the woken task reads and clears them
+            regs.inten().modify(|_, w| {
+                w.ipcmddoneen()
+                    .clear_bit()
+                    .ipcmderren()
+                    .clear_bit()
+                    .ipcmdgeen()
+                    .clear_bit()
+                    .seqtimeouten()
+                    .clear_bit()
+            });
+            info.state.waker.wake();
+        }
+
+        // AHB errors have no command to report them to, so latch and clear them here
+        let mut ahb_errors = 0;
+        if intr.ahbcmderr().bit_is_set() {
+            ahb_errors |= AHB_ERR_CMD;
+        }
+        if intr.ahbcmdge().bit_is_set() {
+            ahb_errors |= AHB_ERR_GRANT;
+        }
+        if intr.ahbbustimeout().bit_is_set() {
+            ahb_errors |= AHB_ERR_BUS_TIMEOUT;
+        }
+        if ahb_errors != 0 && regs.inten().read().ahbcmderren().bit_is_set() {
+            regs.intr().write(|w| {
+                w.ahbcmderr()
+                    .clear_bit_by_one()
+                    .ahbcmdge()
+                    .clear_bit_by_one()
+                    .ahbbustimeout()
+                    .clear_bit_by_one()
+            });
+            regs.inten().modify(|_, w| {
+                w.ahbcmderren()
+                    .clear_bit()
+                    .ahbcmdgeen()
+                    .clear_bit()
+                    .ahbbustimeouten()
+                    .clear_bit()
+            });
+            info.state.ahb_errors.fetch_or(ahb_errors, Ordering::Relaxed);
+            info.state.ahb_waker.wake();
         }
     }
 }
 
-impl Instance for crate::peripherals::FLEXSPI {
-    type Interrupt = crate::interrupt::typelevel::FLEXSPI;
-}
 /// Driver mode.
 #[allow(private_bounds)]
 pub trait Mode: sealed::Sealed {}
@@ -361,6 +732,14 @@ pub struct FlexSpiConfigurationPort {
     flash_port: FlexSpiFlashPort,
     /// Device Instance
     device_instance: FlexSpiFlashPortDeviceInstance,
+    /// Widest bus the constructor wired data pins for
+    bus_width: FlexSpiBusWidth,
+    /// RX watermark level in bytes programmed by `configure_flexspi`
+    rx_watermark: u8,
+    /// TX watermark level in bytes programmed by `configure_flexspi`
+    tx_watermark: u8,
+    /// Leave the AHB write sequence unprogrammed in `configure_device_port`
+    read_only: bool,
     /// FlexSPI HW Info Object
     info: Info,
 }
@@ -373,10 +752,19 @@ pub struct FlexspiNorStorageBus<'d, M: Mode> {
     rx_watermark: u8,
     /// TX FIFO Watermark Level
     tx_watermark: u8,
+    /// Read-only guard
+    read_only: bool,
+    /// Start of the AHB memory mapped window
+    ahb_base: u32,
+    /// DMA channels serving the IP RX and TX FIFO requests, only taken by the async constructors
+    rx_dma: Option<dma::channel::Channel<'d>>,
+    tx_dma: Option<dma::channel::Channel<'d>>,
+    /// An erase was suspended with `erase_suspend` and not resumed yet
+    erase_suspended: bool,
     /// Mode Phantom object
     _mode: core::marker::PhantomData<M>,
     /// FlexSPI Configuration Port
-    pub configport: FlexSpiConfigurationPort,
+    configport: FlexSpiConfigurationPort,
     phantom: core::marker::PhantomData<&'d ()>,
 }
 
@@ -586,6 +974,258 @@ impl FlexSpiError {
     }
 }
 
+// LUT NUM_PADS encoding of the bus width a command is issued with. Every command
+// carries its own width so e.g. a single-lane read-id can coexist with octal reads.
+fn lut_num_pads(bus_width: &NorStorageBusWidth) -> u8 {
+    match bus_width {
+        NorStorageBusWidth::Single => 0,
+        NorStorageBusWidth::Dual => 1,
+        NorStorageBusWidth::Quad => 2,
+        NorStorageBusWidth::Octal => 3,
+    }
+}
+
+impl FlexSpiBusWidth {
+    // LUT NUM_PADS encoding of the widest instruction this bus can carry
+    fn max_num_pads(self) -> u8 {
+        match self {
+            FlexSpiBusWidth::Single => 0,
+            FlexSpiBusWidth::Dual => 1,
+            FlexSpiBusWidth::Quad => 2,
+            FlexSpiBusWidth::Octal => 3,
+        }
+    }
+}
+
+/// Maximum number of instructions in one LUT sequence
+const LUT_SEQ_INSTR_COUNT: u8 = 8;
+
+/// FlexSPI controller and device port initialization error
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FlexspiInitError {
+    /// A configuration value is out of range, e.g. the FIFO watermark or the flash size
+    InvalidConfig,
+    /// The software reset (MCR0[SWRESET]) did not complete
+    ResetTimeout,
+    /// The arbiter and sequence engine did not go idle
+    IdleTimeout,
+    /// The sample clock DLL did not lock, usually a sign of a wrong FlexSPI root clock. Only
+    /// returned when sampling on the DQS input at 100MHz or above, the one case the DLL is
+    /// enabled.
+    DllLockTimeout,
+}
+
+// Poll `done` until it returns true. Gives up after `timeout` milliseconds. Without a time
+// driver the polls are spaced by a 1us cycle delay sized for the fastest core clock, so the
+// timeout is a lower bound that only grows at slower clocks.
+#[cfg(feature = "time")]
+fn poll_until(timeout: u64, mut done: impl FnMut() -> bool) -> bool {
+    let start = Instant::now();
+    while !done() {
+        if is_expired(start, timeout) {
+            return false;
+        }
+    }
+    true
+}
+
+#[cfg(not(feature = "time"))]
+fn poll_until(timeout: u64, mut done: impl FnMut() -> bool) -> bool {
+    (0..timeout * 1000).any(|_| {
+        if done() {
+            return true;
+        }
+        delay_loop_clocks(1, CORE_CLOCK_MAX_HZ);
+        false
+    })
+}
+
+/// LUT sequence builder error
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LutBuilderError {
+    /// More than 8 instructions were appended
+    TooManyInstructions,
+    /// A DUMMY instruction does not match the SDR/DDR mode of the command
+    DummyModeMismatch,
+}
+
+/// Builds a raw FlexSPI LUT sequence out of typed instructions
+///
+/// Two instructions are packed per LUT word, so a sequence holds up to 8 of them. The SDR/DDR
+/// mode is taken from the first CMD instruction and every DUMMY instruction must use the same
+/// mode, as the controller rejects the sequence otherwise.
+pub struct LutSequenceBuilder {
+    seq: [u32; 4],
+    count: u8,
+    ddr: Option<bool>,
+    error: Option<LutBuilderError>,
+}
+
+impl Default for LutSequenceBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LutSequenceBuilder {
+    /// Create an empty sequence
+    pub const fn new() -> Self {
+        Self {
+            seq: [0; 4],
+            count: 0,
+            ddr: None,
+            error: None,
+        }
+    }
+
+    /// Append one instruction issued on `pads` with the given operand
+    ///
+    /// Errors are latched and reported by [`Self::build`], so calls can be chained.
+    pub fn instr(mut self, opcode: FlexSpiLutOpcode, pads: NorStorageBusWidth, operand: u8) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        if self.count >= LUT_SEQ_INSTR_COUNT {
+            self.error = Some(LutBuilderError::TooManyInstructions);
+            return self;
+        }
+
+        let op = opcode as u8;
+        if self.ddr.is_none() && (op == CMD_SDR as u8 || op == CMD_DDR as u8) {
+            self.ddr = Some(op == CMD_DDR as u8);
+        }
+        let dummy_ddr = (op == DUMMY_DDR as u8).then_some(true);
+        let dummy_sdr = (op == DUMMY_SDR as u8).then_some(false);
+        if let (Some(ddr), Some(dummy)) = (self.ddr, dummy_ddr.or(dummy_sdr)) {
+            if ddr != dummy {
+                self.error = Some(LutBuilderError::DummyModeMismatch);
+                return self;
+            }
+        }
+
+        let encoded = ((op as u32) << 10) | ((lut_num_pads(&pads) as u32) << 8) | operand as u32;
+        let shift = if self.count % 2 == 0 { 0 } else { 16 };
+        self.seq[(self.count / 2) as usize] |= encoded << shift;
+        self.count += 1;
+        self
+    }
+
+    /// Finish the sequence and return the 4 LUT words
+    ///
+    /// Unused slots are left as STOP (all zeroes).
+    pub fn build(self) -> Result<[u32; 4], LutBuilderError> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(self.seq),
+        }
+    }
+}
+
+// Operand of the DUMMY_SDR/DUMMY_DDR instruction.
+//
+// `Clocks` is the raw LUT operand and is used as is. DUMMY_SDR counts serial clock cycles but
+// DUMMY_DDR counts half cycles, so a DDR command passes about twice the datasheet cycle count,
+// e.g. `Clocks(0x29)` for the 20 cycle octal DDR read. `Bytes` is converted to serial clock
+// cycles using the bits moved per clock on the command's bus width, rounding up so a partial
+// clock is never dropped, and doubled into half cycles for DDR.
+fn dummy_clocks(cmd: &NorStorageCmd) -> u8 {
+    match cmd.dummy {
+        NorStorageDummyCycles::Clocks(clocks) => clocks,
+        NorStorageDummyCycles::Bytes(bytes) => {
+            let mut bits_per_clock = 1 << lut_num_pads(&cmd.bus_width);
+            let mut operand_per_clock = 1;
+            if cmd.mode == NorStorageCmdMode::DDR {
+                bits_per_clock *= 2;
+                operand_per_clock = 2;
+            }
+            let clocks = (bytes as u16 * 8).div_ceil(bits_per_clock);
+            (clocks * operand_per_clock).min(u8::MAX as u16) as u8
+        }
+    }
+}
+
+// The IP FIFOs are programmed in 64 bit units and hold MAX_TRANSFER_SIZE bytes
+fn is_valid_watermark(watermark: u8) -> bool {
+    watermark != 0 && watermark % 8 == 0 && watermark as u32 <= MAX_TRANSFER_SIZE
+}
+
+// Pack up to one FIFO slot of `chunk` into a TFDR word, in the byte order RFDR slots are
+// unpacked with. A partial last word is zero padded.
+fn tx_fifo_word(chunk: &[u8]) -> u32 {
+    let mut word = [0_u8; FIFO_SLOT_SIZE as usize];
+    let len = chunk.len().min(word.len());
+    word[..len].copy_from_slice(&chunk[..len]);
+    u32::from_le_bytes(word)
+}
+
+// Single lane SDR command made of the opcode alone, e.g. erase suspend or 4-byte address
+// mode entry
+fn opcode_only_cmd(opcode: u8) -> NorStorageCmd {
+    NorStorageCmd {
+        cmd_lb: opcode,
+        cmd_ub: None,
+        addr: None,
+        addr_width: None,
+        bus_width: NorStorageBusWidth::Single,
+        mode: NorStorageCmdMode::SDR,
+        dummy: NorStorageDummyCycles::Clocks(0),
+        cmdtype: None,
+        data_bytes: None,
+    }
+}
+
+// Reject commands whose fields contradict each other, which would otherwise be programmed
+// into a LUT sequence the controller fails at run time
+fn check_cmd(cmd: &NorStorageCmd) -> Result<(), NorStorageBusError> {
+    let addr_ok = match (cmd.addr, cmd.addr_width) {
+        // An address without a width would silently be dropped from the sequence
+        (Some(_), None | Some(0)) => false,
+        (_, Some(width)) => width <= 32,
+        (None, None) => true,
+    };
+    // A data phase needs a length, and a length needs a data phase
+    let data_ok = match (cmd.cmdtype, cmd.data_bytes) {
+        (Some(_), None) => false,
+        (None, Some(len)) => len == 0,
+        _ => true,
+    };
+
+    if addr_ok && data_ok {
+        Ok(())
+    } else {
+        Err(NorStorageBusError::StorageBusInternalError)
+    }
+}
+
+fn is_modifying_cmd(cmd: &NorStorageCmd) -> bool {
+    matches!(cmd.cmdtype, Some(NorStorageCmdType::Write)) || MODIFYING_OPCODES.contains(&cmd.cmd_lb)
+}
+
+// Whether any instruction of `seq` is a write data phase or starts a command with a modifying
+// opcode. The CMD instruction following another one is skipped, in octal DDR mode it carries
+// the inverted opcode.
+fn is_modifying_seq(seq: &[u32; 4]) -> bool {
+    let mut prev_cmd = false;
+
+    for instr in seq.iter().flat_map(|word| [*word as u16, (*word >> 16) as u16]) {
+        let opcode = (instr >> 10) as u8;
+        let operand = instr as u8;
+        let is_cmd = opcode == CMD_SDR as u8 || opcode == CMD_DDR as u8;
+
+        if opcode == WRITE_SDR as u8 || opcode == WRITE_DDR as u8 {
+            return true;
+        }
+        if is_cmd && !prev_cmd && MODIFYING_OPCODES.contains(&operand) {
+            return true;
+        }
+        prev_cmd = is_cmd;
+    }
+
+    false
+}
+
 impl<'d> BlockingNorStorageBusDriver for FlexspiNorStorageBus<'d, Blocking> {
     fn send_command(
         &mut self,
@@ -593,6 +1233,20 @@ impl<'d> BlockingNorStorageBusDriver for FlexspiNorStorageBus<'d, Blocking> {
         read_buf: Option<&mut [u8]>,
         write_buf: Option<&[u8]>,
     ) -> Result<(), NorStorageBusError> {
+        // Catch malformed commands before they reach the LUT
+        check_cmd(&cmd)?;
+
+        // A read-only bus never programs a modifying command into the LUT
+        if self.read_only && is_modifying_cmd(&cmd) {
+            return Err(NorStorageBusError::StorageBusNotAvailable);
+        }
+
+        // A command wider than the wired data pins would drive pads that are not connected
+        self.check_bus_width(&cmd)?;
+
+        // A watermark the FIFOs cannot be programmed with would underflow the watermark fields
+        self.check_watermarks()?;
+
         // Setup the transfer to be sent of the FlexSPI IP Port
         self.setup_ip_transfer(OPERATION_SEQ_NUMBER, cmd.addr, cmd.data_bytes);
 
@@ -602,81 +1256,238 @@ impl<'d> BlockingNorStorageBusDriver for FlexspiNorStorageBus<'d, Blocking> {
         // Start the transfer
         self.execute_ip_cmd();
 
-        // Wait for command to complete
+        // Wait for command to complete, surfacing any controller error raised for it
         // This wait is for FlexSPI to send the command to the Flash device
         // But the command completion in the flash needs to be checked separately
         // by reading the status register of the flash device
-        let status = self.wait_for_cmd_completion();
-        if status.is_err() {
-            return status;
-        }
-
-        // Check for any errors during the transfer
-        self.check_transfer_status().map_err(|e| {
-            e.describe(self);
-            <FlexSpiError as Into<FlexSpiError>>::into(e)
-        })?;
+        self.wait_for_cmd_completion()?;
 
         // For data transfer commands, read/write the data
         if let Some(data_cmd) = cmd.cmdtype {
             match data_cmd {
                 NorStorageCmdType::Read => {
                     let buffer = read_buf.ok_or(NorStorageBusError::StorageBusInternalError)?;
-                    self.read_data(cmd, buffer)?;
+                    self.read_data(&cmd, buffer)?;
                 }
                 NorStorageCmdType::Write => {
                     let buffer = write_buf.ok_or(NorStorageBusError::StorageBusInternalError)?;
-                    self.write_data(cmd, buffer)?;
+                    self.write_data(&cmd, buffer)?;
                 }
             }
         }
+
+        // Prefetched AHB data may predate the program or erase just issued
+        if is_modifying_cmd(&cmd) {
+            self.flush_ahb_buffers()?;
+        }
+
         Ok(())
     }
 }
 
-impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
-    fn setup_ip_transfer(&mut self, seq_id: u8, addr: Option<u32>, size: Option<u32>) {
-        self.info.regs.ipcr0().modify(|_, w| unsafe {
-            //SAFETY - We are writing the address register. There is no issue from safety perspective
-            w.sfar().bits(addr.unwrap_or(0))
-        });
+impl<'d> FlexspiNorStorageBus<'d, Blocking> {
+    /// Send `cmd` with the serial clock at `speed`, restoring the previous speed afterwards
+    ///
+    /// Useful on marginal boards to run e.g. status register reads at half speed while array
+    /// reads stay at full speed. The speed is restored even if the command fails.
+    pub fn send_command_at_speed(
+        &mut self,
+        cmd: NorStorageCmd,
+        read_buf: Option<&mut [u8]>,
+        write_buf: Option<&[u8]>,
+        speed: FlexspiAccessSpeed,
+    ) -> Result<(), NorStorageBusError> {
+        let previous = self.set_access_speed(speed)?;
+        let result = self.send_command(cmd, read_buf, write_buf);
+        self.set_access_speed(previous)?;
 
-        // Set the Command sequence ID
+        result
+    }
 
-        self.info.regs.ipcr1().modify(|_, w| unsafe {
-            // SAFETY: Operation is safe as we are programming the sequence ID to be used for the transfer
-            w.iseqid().bits(seq_id as u8)
-        });
+    /// Read `out.len()` bytes of the JEDEC SFDP tables starting at `addr`
+    ///
+    /// Issues the single lane SDR 0x5A command with a 3 byte address and 8 dummy clocks, so the
+    /// flash must be in SPI mode. The read is split into IP transfers of at most 128 bytes.
+    pub fn read_sfdp(&mut self, addr: u32, out: &mut [u8]) -> Result<(), NorStorageBusError> {
+        addr.checked_add(out.len() as u32)
+            .filter(|end| *end <= 1 << 24)
+            .ok_or(NorStorageBusError::StorageBusInternalError)?;
+
+        for (i, chunk) in out.chunks_mut(MAX_TRANSFER_SIZE as usize).enumerate() {
+            let cmd = NorStorageCmd {
+                cmd_lb: SFDP_READ_OPCODE,
+                cmd_ub: None,
+                addr: Some(addr + i as u32 * MAX_TRANSFER_SIZE),
+                addr_width: Some(24),
+                bus_width: NorStorageBusWidth::Single,
+                mode: NorStorageCmdMode::SDR,
+                dummy: NorStorageDummyCycles::Clocks(SFDP_DUMMY_CLOCKS),
+                cmdtype: Some(NorStorageCmdType::Read),
+                data_bytes: Some(chunk.len() as u32),
+            };
+            self.send_command(cmd, Some(chunk), None)?;
+        }
 
-        // Reset the sequence pointer
-        self.info.regs.flshcr2(0).modify(|_, w| w.clrinstrptr().set_bit());
-        self.info.regs.flshcr2(1).modify(|_, w| w.clrinstrptr().set_bit());
-        self.info.regs.flshcr2(2).modify(|_, w| w.clrinstrptr().set_bit());
-        self.info.regs.flshcr2(3).modify(|_, w| w.clrinstrptr().set_bit());
+        Ok(())
+    }
 
-        // Disable DMA for TX and RX and Reset RX and TX FIFO
-        self.info
-            .regs
-            .iptxfcr()
-            .modify(|_, w| w.txdmaen().clear_bit().clriptxf().set_bit());
-        self.info
-            .regs
-            .iprxfcr()
-            .modify(|_, w| w.rxdmaen().clear_bit().clriprxf().set_bit());
+    /// Suspend the sector or block erase running in the flash
+    ///
+    /// Issues the single lane SDR 0x75 command. The flash needs its suspend latency (tSUS,
+    /// typically 20-30us) before reads of other sectors return data; poll the device specific
+    /// suspend status bit if the exact point matters. Does nothing if already suspended.
+    pub fn erase_suspend(&mut self) -> Result<(), NorStorageBusError> {
+        if self.erase_suspended {
+            return Ok(());
+        }
 
-        // TODO: Set Tx and Rx watermark
-        self.info.regs.iprxfcr().modify(|_, w| unsafe {
-            // SAFETY: Operation is safe as we are programming the watermark value to be used for the transfer
-            w.rxwmrk().bits((self.rx_watermark / 8) - 1 as u8)
-        });
+        self.send_command(opcode_only_cmd(ERASE_SUSPEND_OPCODE), None, None)?;
+        self.erase_suspended = true;
 
-        // Set the data length
-        // Max RX FIFO size is MAX_FLEXSPI_TRANSFER_SIZE bytes
-        // TODO - We want to avoid RX FIFO overflow for now. We will revisit this later and increase the size
-        // once we add overflow handling
-        if let Some(size) = size {
-            self.info.regs.ipcr1().modify(|_, w| unsafe {
-                // SAFETY: Operation is safe as we are programming the size of the transfer
+        Ok(())
+    }
+
+    /// Resume an erase suspended with [`Self::erase_suspend`]
+    ///
+    /// Issues the single lane SDR 0x7A command; the busy bit is set again until the erase
+    /// completes. Does nothing if no erase is suspended.
+    pub fn erase_resume(&mut self) -> Result<(), NorStorageBusError> {
+        if !self.erase_suspended {
+            return Ok(());
+        }
+
+        self.send_command(opcode_only_cmd(ERASE_RESUME_OPCODE), None, None)?;
+        self.erase_suspended = false;
+
+        Ok(())
+    }
+
+    /// Switch the flash between 3 and 4 byte addressing with the single lane SDR 0xB7 (enter)
+    /// and 0xE9 (exit) commands
+    ///
+    /// The address phase of every command is taken from its `addr_width`, so commands sent
+    /// afterwards must use `Some(32)` in 4 byte mode and `Some(24)` in 3 byte mode. Some parts
+    /// need a write enable before these commands; check the datasheet.
+    pub fn set_4byte_address_mode(&mut self, enable: bool) -> Result<(), NorStorageBusError> {
+        let opcode = if enable {
+            ENTER_4BYTE_ADDR_OPCODE
+        } else {
+            EXIT_4BYTE_ADDR_OPCODE
+        };
+
+        self.send_command(opcode_only_cmd(opcode), None, None)
+    }
+}
+
+impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
+    /// Whether an erase was suspended through this bus and not resumed yet
+    pub fn is_erase_suspended(&self) -> bool {
+        self.erase_suspended
+    }
+
+    /// Change the serial clock speed (MCR0.HSEN) and return the previous one
+    ///
+    /// The controller is idled and put in module stop mode while the setting is changed.
+    fn set_access_speed(&mut self, speed: FlexspiAccessSpeed) -> Result<FlexspiAccessSpeed, NorStorageBusError> {
+        let regs = self.info.regs;
+        let previous = if regs.mcr0().read().hsen().bit_is_set() {
+            FlexspiAccessSpeed::Half
+        } else {
+            FlexspiAccessSpeed::Full
+        };
+
+        if previous != speed {
+            self.wait_for_idle()?;
+
+            regs.mcr0().modify(|_, w| w.mdis().set_bit());
+            regs.mcr0()
+                .modify(|_, w| w.hsen().bit(speed == FlexspiAccessSpeed::Half));
+            regs.mcr0().modify(|_, w| w.mdis().clear_bit());
+        }
+
+        Ok(previous)
+    }
+
+    /// Change the RX and TX FIFO watermark levels used for IP command data
+    ///
+    /// Both levels are in bytes and must be a non zero multiple of 8 no larger than the
+    /// 128 byte FIFO. Larger watermarks mean fewer FIFO refills on long transfers.
+    pub fn set_watermarks(&mut self, rx_watermark: u8, tx_watermark: u8) -> Result<(), NorStorageBusError> {
+        if !is_valid_watermark(rx_watermark) || !is_valid_watermark(tx_watermark) {
+            return Err(NorStorageBusError::StorageBusInternalError);
+        }
+
+        self.rx_watermark = rx_watermark;
+        self.tx_watermark = tx_watermark;
+        self.configport.rx_watermark = rx_watermark;
+        self.configport.tx_watermark = tx_watermark;
+
+        Ok(())
+    }
+
+    fn check_bus_width(&self, cmd: &NorStorageCmd) -> Result<(), NorStorageBusError> {
+        if lut_num_pads(&cmd.bus_width) <= self.configport.bus_width.max_num_pads() {
+            Ok(())
+        } else {
+            Err(NorStorageBusError::StorageBusNotAvailable)
+        }
+    }
+
+    fn check_watermarks(&self) -> Result<(), NorStorageBusError> {
+        if is_valid_watermark(self.rx_watermark) && is_valid_watermark(self.tx_watermark) {
+            Ok(())
+        } else {
+            Err(NorStorageBusError::StorageBusInternalError)
+        }
+    }
+
+    fn setup_ip_transfer(&mut self, seq_id: u8, addr: Option<u32>, size: Option<u32>) {
+        self.info.regs.ipcr0().modify(|_, w| unsafe {
+            //SAFETY - We are writing the address register. There is no issue from safety perspective
+            w.sfar().bits(addr.unwrap_or(0))
+        });
+
+        // Set the Command sequence ID
+
+        self.info.regs.ipcr1().modify(|_, w| unsafe {
+            // SAFETY: Operation is safe as we are programming the sequence ID to be used for the transfer
+            w.iseqid().bits(seq_id as u8)
+        });
+
+        // Reset the sequence pointer of the active device only, the other devices may be
+        // serving AHB accesses
+        self.info
+            .regs
+            .flshcr2(self.configport.device_index())
+            .modify(|_, w| w.clrinstrptr().set_bit());
+
+        // Disable DMA for TX and RX and Reset RX and TX FIFO
+        self.info
+            .regs
+            .iptxfcr()
+            .modify(|_, w| w.txdmaen().clear_bit().clriptxf().set_bit());
+        self.info
+            .regs
+            .iprxfcr()
+            .modify(|_, w| w.rxdmaen().clear_bit().clriprxf().set_bit());
+
+        // Set Tx and Rx watermark, both registers count in 64 bit units
+        self.info.regs.iprxfcr().modify(|_, w| unsafe {
+            // SAFETY: Operation is safe as we are programming the watermark value to be used for the transfer
+            w.rxwmrk().bits((self.rx_watermark / 8) - 1 as u8)
+        });
+        self.info.regs.iptxfcr().modify(|_, w| unsafe {
+            // SAFETY: Operation is safe as we are programming the watermark value to be used for the transfer
+            w.txwmrk().bits((self.tx_watermark / 8) - 1 as u8)
+        });
+
+        // Set the data length
+        // Max RX FIFO size is MAX_FLEXSPI_TRANSFER_SIZE bytes
+        // TODO - We want to avoid RX FIFO overflow for now. We will revisit this later and increase the size
+        // once we add overflow handling
+        if let Some(size) = size {
+            self.info.regs.ipcr1().modify(|_, w| unsafe {
+                // SAFETY: Operation is safe as we are programming the size of the transfer
                 w.idatsz().bits(min(size, MAX_TRANSFER_SIZE) as u16)
             });
         }
@@ -690,9 +1501,9 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
         let intr = self.info.regs.intr().read();
 
         if intr.ipcmderr().bit_is_set() {
-            self.info.regs.intr().modify(|_, w| w.ipcmderr().clear_bit_by_one());
+            self.info.regs.intr().write(|w| w.ipcmderr().clear_bit_by_one());
             if intr.seqtimeout().bit_is_set() {
-                self.info.regs.intr().modify(|_, w| w.seqtimeout().clear_bit_by_one());
+                self.info.regs.intr().write(|w| w.seqtimeout().clear_bit_by_one());
                 return Err(FlexSpiError::CmdExecErr {
                     result: CmdResult {
                         AhbReadCmdErr: false,
@@ -710,8 +1521,9 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
                 });
             }
         } else if intr.ahbcmderr().bit_is_set() {
-            self.info.regs.intr().modify(|_, w| w.ahbcmderr().clear_bit_by_one());
+            self.info.regs.intr().write(|w| w.ahbcmderr().clear_bit_by_one());
             if intr.seqtimeout().bit_is_set() {
+                self.info.regs.intr().write(|w| w.seqtimeout().clear_bit_by_one());
                 return Err(FlexSpiError::CmdExecErr {
                     result: CmdResult {
                         AhbReadCmdErr: true,
@@ -729,10 +1541,7 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
                 });
             }
         } else if intr.ahbbustimeout().bit_is_set() {
-            self.info
-                .regs
-                .intr()
-                .modify(|_, w| w.ahbbustimeout().clear_bit_by_one());
+            self.info.regs.intr().write(|w| w.ahbbustimeout().clear_bit_by_one());
             return Err(FlexSpiError::AhbBusTimeout {
                 result: CmdResult {
                     AhbReadCmdErr: true,
@@ -741,13 +1550,10 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
                 },
             });
         } else if intr.datalearnfail().bit_is_set() {
-            self.info
-                .regs
-                .intr()
-                .modify(|_, w| w.datalearnfail().clear_bit_by_one());
+            self.info.regs.intr().write(|w| w.datalearnfail().clear_bit_by_one());
             return Err(FlexSpiError::DataLearningFailed);
         } else if intr.ipcmdge().bit_is_set() {
-            self.info.regs.intr().modify(|_, w| w.ipcmdge().clear_bit_by_one());
+            self.info.regs.intr().write(|w| w.ipcmdge().clear_bit_by_one());
             return Err(FlexSpiError::CmdGrantErr {
                 result: CmdResult {
                     AhbReadCmdErr: false,
@@ -756,7 +1562,7 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
                 },
             });
         } else if intr.ahbcmdge().bit_is_set() {
-            self.info.regs.intr().modify(|_, w| w.ahbcmdge().clear_bit_by_one());
+            self.info.regs.intr().write(|w| w.ahbcmdge().clear_bit_by_one());
             return Err(FlexSpiError::CmdGrantErr {
                 result: CmdResult {
                     AhbReadCmdErr: true,
@@ -807,12 +1613,7 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
         if cmd.mode == NorStorageCmdMode::SDR {
             cmd_mode = CMD_SDR;
         }
-        let bus_width = match cmd.bus_width {
-            NorStorageBusWidth::Single => 0,
-            NorStorageBusWidth::Dual => 1,
-            NorStorageBusWidth::Quad => 2,
-            NorStorageBusWidth::Octal => 3,
-        };
+        let bus_width = lut_num_pads(&cmd.bus_width);
 
         self.write_instr(cookie, cmd_mode, cmd.cmd_lb, bus_width);
 
@@ -830,12 +1631,7 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
         if cmd.mode == NorStorageCmdMode::SDR {
             cmd_mode = RADDR_SDR;
         }
-        let bus_width = match cmd.bus_width {
-            NorStorageBusWidth::Single => 0,
-            NorStorageBusWidth::Dual => 1,
-            NorStorageBusWidth::Quad => 2,
-            NorStorageBusWidth::Octal => 3,
-        };
+        let bus_width = lut_num_pads(&cmd.bus_width);
         self.write_instr(cookie, cmd_mode, cmd.addr_width.unwrap(), bus_width);
 
         cookie.next_instruction();
@@ -847,22 +1643,9 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
         if cmd.mode == NorStorageCmdMode::SDR {
             cmd_mode = DUMMY_SDR;
         }
-        let bus_width = match cmd.bus_width {
-            NorStorageBusWidth::Single => 0,
-            NorStorageBusWidth::Dual => 1,
-            NorStorageBusWidth::Quad => 2,
-            NorStorageBusWidth::Octal => 3,
-        };
-        let dummy_val: u8;
+        let bus_width = lut_num_pads(&cmd.bus_width);
+        let dummy_val = dummy_clocks(cmd);
 
-        match cmd.dummy {
-            NorStorageDummyCycles::Bytes(dummy_bytes) => {
-                dummy_val = dummy_bytes;
-            }
-            NorStorageDummyCycles::Clocks(dummy_cycles) => {
-                dummy_val = dummy_cycles;
-            }
-        }
         self.write_instr(cookie, cmd_mode, dummy_val, bus_width);
         cookie.next_instruction();
     }
@@ -873,12 +1656,7 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
         if cmd.mode == NorStorageCmdMode::SDR {
             cmd_mode = READ_SDR;
         }
-        let bus_width = match cmd.bus_width {
-            NorStorageBusWidth::Single => 0,
-            NorStorageBusWidth::Dual => 1,
-            NorStorageBusWidth::Quad => 2,
-            NorStorageBusWidth::Octal => 3,
-        };
+        let bus_width = lut_num_pads(&cmd.bus_width);
 
         self.write_instr(cookie, cmd_mode, data_length, bus_width);
 
@@ -891,12 +1669,7 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
         if cmd.mode == NorStorageCmdMode::SDR {
             cmd_mode = WRITE_SDR;
         }
-        let bus_width = match cmd.bus_width {
-            NorStorageBusWidth::Single => 0,
-            NorStorageBusWidth::Dual => 1,
-            NorStorageBusWidth::Quad => 2,
-            NorStorageBusWidth::Octal => 3,
-        };
+        let bus_width = lut_num_pads(&cmd.bus_width);
 
         self.write_instr(cookie, cmd_mode, data_length, bus_width);
 
@@ -944,26 +1717,22 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
 
         self.program_cmd_instruction(cmd, &mut cookie);
 
-        if cmd.addr_width.is_some() {
+        // A zero width address phase would make the controller reject the sequence
+        if cmd.addr_width.is_some_and(|width| width > 0) {
             self.program_addr_instruction(cmd, &mut cookie);
         }
 
-        match cmd.dummy {
-            NorStorageDummyCycles::Clocks(clk) => {
-                if clk > 0 {
-                    self.program_dummy_instruction(cmd, &mut cookie);
-                }
-            }
-            _ => {}
+        if dummy_clocks(cmd) > 0 {
+            self.program_dummy_instruction(cmd, &mut cookie);
         }
 
         if let Some(transfertype) = cmd.cmdtype {
             match transfertype {
                 NorStorageCmdType::Read => {
-                    self.program_read_data_instruction(cmd, &mut cookie, cmd.data_bytes.unwrap() as u8);
+                    self.program_read_data_instruction(cmd, &mut cookie, cmd.data_bytes.unwrap_or(0) as u8);
                 }
                 NorStorageCmdType::Write => {
-                    self.program_write_data_instruction(cmd, &mut cookie, cmd.data_bytes.unwrap() as u8);
+                    self.program_write_data_instruction(cmd, &mut cookie, cmd.data_bytes.unwrap_or(0) as u8);
                 }
             }
         }
@@ -979,482 +1748,1553 @@ impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
     }
 }
 
-impl<'d> FlexspiNorStorageBus<'d, Blocking> {
-    fn read_data(&mut self, cmd: NorStorageCmd, read_buf: &mut [u8]) -> Result<(), NorStorageBusError> {
-        let size = cmd.data_bytes.ok_or(NorStorageBusError::StorageBusInternalError)?;
+impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
+    /// Configuration port of this bus
+    pub fn configport(&self) -> &FlexSpiConfigurationPort {
+        &self.configport
+    }
 
-        if read_buf.len() != size as usize {
-            return Err(NorStorageBusError::StorageBusInternalError);
+    /// Configuration port of this bus, for calls that reconfigure the controller
+    ///
+    /// Reconfiguring may change the AHB mapping, so this needs exclusive access to the bus.
+    pub fn configport_mut(&mut self) -> &mut FlexSpiConfigurationPort {
+        &mut self.configport
+    }
+
+    /// Select the alias the AHB window is reached at, [`FlexspiAhbAlias::NonSecure`] by default
+    ///
+    /// Use this when the window must be accessed through the secure alias.
+    pub fn set_ahb_base(&mut self, alias: FlexspiAhbAlias) {
+        self.ahb_base = alias.base();
+    }
+
+    /// Address of the first byte of this device in the AHB window
+    ///
+    /// The controller maps the devices back to back in A1, A2, B1, B2 order using their
+    /// programmed sizes, so this depends on the device port configuration.
+    pub fn mapped_base(&self) -> u32 {
+        self.ahb_base.wrapping_add(self.configport.window_offset())
+    }
+
+    /// AHB address of `len` bytes at `offset`, if they lie within this device's part of the window
+    fn mapped_addr(&self, offset: u32, len: usize) -> Result<u32, NorStorageBusError> {
+        let end = u32::try_from(len).ok().and_then(|len| offset.checked_add(len));
+
+        match end {
+            Some(end) if end <= self.configport.mapped_size() => Ok(self.mapped_base().wrapping_add(offset)),
+            _ => Err(NorStorageBusError::StorageBusInternalError),
         }
+    }
 
-        for chunk in read_buf.chunks_mut(MAX_TRANSFER_SIZE as usize) {
-            self.read_cmd_data(chunk)?;
+    /// The whole device as a slice over the AHB memory mapped window
+    ///
+    /// Spans `mapped_size()` bytes from [`Self::mapped_base`], so indexing past the device
+    /// panics like any slice access. Commands that modify the flash and calls that change the
+    /// mapping, e.g. through [`Self::configport_mut`], need `&mut self`, so neither the contents
+    /// nor the mapping can change while the slice is borrowed.
+    pub fn as_mapped_slice(&self) -> &[u8] {
+        let len = self.configport.mapped_size() as usize;
+
+        // SAFETY: The AHB window of this device is always readable and `len` bytes long as
+        // programmed in FLSHSZ. Everything that programs or erases the flash or resets the
+        // controller takes `&mut self` or `&mut FlexSpiConfigurationPort`, which only this bus
+        // can hand out, so the borrow of `self` keeps the memory unchanged.
+        unsafe { core::slice::from_raw_parts(self.mapped_base() as *const u8, len) }
+    }
+
+    /// Read `read_buf.len()` bytes starting at `offset` through the AHB memory mapped window
+    ///
+    /// A range that does not fit in [`FlexSpiConfigurationPort::mapped_size`] is rejected with
+    /// `StorageBusInternalError`.
+    pub fn read_mapped(&self, offset: u32, read_buf: &mut [u8]) -> Result<(), NorStorageBusError> {
+        let start = self.mapped_addr(offset, read_buf.len())?;
+
+        let src = start as *const u8;
+        for (i, byte) in read_buf.iter_mut().enumerate() {
+            // SAFETY: The AHB window is a read-only view of the flash, byte reads of any
+            // address within it are always valid
+            *byte = unsafe { core::ptr::read_volatile(src.add(i)) };
         }
 
         Ok(())
     }
 
-    fn write_data(&mut self, cmd: NorStorageCmd, write_buf: &[u8]) -> Result<(), NorStorageBusError> {
-        let size = cmd.data_bytes.ok_or(NorStorageBusError::StorageBusInternalError)?;
+    /// Program `data` at `offset` through the AHB memory mapped window
+    ///
+    /// The device port must have been configured with an AHB write sequence
+    /// (`awr_seq_index`/`awr_seq_number`) that issues write enable followed by page program,
+    /// and AHB writes must be enabled. `offset` and `data.len()` must be 4 byte aligned and the
+    /// range must not cross a program page boundary of the device. A range that does not fit in
+    /// [`FlexSpiConfigurationPort::mapped_size`] is rejected with `StorageBusInternalError`.
+    ///
+    /// Once the controller is idle again the AHB buffers are flushed so following mapped reads
+    /// fetch fresh data. The flash itself may still be busy programming: poll its status before
+    /// checking the result with [`Self::verify_mapped`].
+    pub fn write_mapped(&mut self, offset: u32, data: &[u8]) -> Result<(), NorStorageBusError> {
+        if self.read_only {
+            return Err(NorStorageBusError::StorageBusNotAvailable);
+        }
 
-        if write_buf.len() != size as usize {
+        if offset % FIFO_SLOT_SIZE != 0 || data.len() % FIFO_SLOT_SIZE as usize != 0 {
             return Err(NorStorageBusError::StorageBusInternalError);
         }
 
-        for chunk in write_buf.chunks(MAX_TRANSFER_SIZE as usize) {
-            self.write_cmd_data(chunk)?;
+        let start = self.mapped_addr(offset, data.len())?;
+
+        let dst = start as *mut u32;
+        for (i, word) in data.chunks_exact(FIFO_SLOT_SIZE as usize).enumerate() {
+            let value = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+            // SAFETY: The destination lies in the AHB window of the flash and is word aligned. The
+            // write is turned into the configured AHB write sequence by the controller.
+            unsafe { core::ptr::write_volatile(dst.add(i), value) };
+        }
+
+        // Make sure the bufferable writes have left the core before waiting on the controller
+        cortex_m::asm::dsb();
+        self.wait_for_idle()?;
+
+        // Check the result before the flush resets the controller
+        self.check_transfer_status().map_err(|e| {
+            e.describe(self);
+            <FlexSpiError as Into<NorStorageBusError>>::into(e)
+        })?;
+
+        self.flush_ahb_buffers()
+    }
+
+    /// Program `data` at `offset` through the AHB memory mapped window, split at the program
+    /// page boundaries of `geometry`
+    ///
+    /// Every page is written with [`Self::write_mapped`], so the same AHB write sequence and
+    /// alignment requirements apply. `wait_ready` is called after each page and must only return
+    /// once the flash has finished programming it, typically by polling the status register.
+    pub fn write_mapped_pages(
+        &mut self,
+        offset: u32,
+        data: &[u8],
+        geometry: &FlexspiNorGeometry,
+        mut wait_ready: impl FnMut(&mut Self) -> Result<(), NorStorageBusError>,
+    ) -> Result<(), NorStorageBusError> {
+        let mut addr = offset;
+        let mut remaining = data;
+
+        while !remaining.is_empty() {
+            let len = remaining.len().min(geometry.page_remaining(addr) as usize);
+            let (page, rest) = remaining.split_at(len);
+
+            self.write_mapped(addr, page)?;
+            wait_ready(self)?;
+
+            addr += len as u32;
+            remaining = rest;
         }
 
         Ok(())
     }
 
-    fn wait_for_cmd_completion(&mut self) -> Result<(), NorStorageBusError> {
-        #[cfg(feature = "time")]
-        {
-            let start = Instant::now();
-            while self.info.regs.intr().read().ipcmddone().bit_is_clear() {
-                let timedout = is_expired(start, CMD_COMPLETION_TIMEOUT);
-                if timedout {
-                    return Err(NorStorageBusError::StorageBusIoError);
-                }
+    /// Compare the content of the AHB memory mapped window at `offset` against `expected`
+    ///
+    /// Returns `StorageBusIoError` on the first mismatch.
+    pub fn verify_mapped(&self, offset: u32, expected: &[u8]) -> Result<(), NorStorageBusError> {
+        // Reject the whole range up front, the chunk offsets below then cannot overflow
+        self.mapped_addr(offset, expected.len())?;
+
+        let mut buf = [0_u8; 16];
+
+        for (i, chunk) in expected.chunks(buf.len()).enumerate() {
+            let read = &mut buf[..chunk.len()];
+            self.read_mapped(offset + (i * 16) as u32, read)?;
+
+            if read != chunk {
+                return Err(NorStorageBusError::StorageBusIoError);
             }
         }
-        #[cfg(not(feature = "time"))]
-        {
-            while self.info.regs.intr().read().ipcmddone().bit_is_clear() {}
-        }
 
         Ok(())
     }
 
-    fn read_cmd_data(&mut self, read_data: &mut [u8]) -> Result<(), NorStorageBusError> {
-        let num_rx_watermark_slot;
-        let mut size = read_data.len() as u32;
+    fn wait_for_idle(&self) -> Result<(), NorStorageBusError> {
+        self.configport
+            .wait_bus_idle()
+            .map_err(|_| NorStorageBusError::StorageBusIoError)
+    }
 
-        let error = self.check_transfer_status();
+    fn flush_ahb_buffers(&mut self) -> Result<(), NorStorageBusError> {
+        self.configport
+            .flush_ahb_buffers()
+            .map_err(|_| NorStorageBusError::StorageBusIoError)
+    }
 
-        if let Err(e) = error {
-            e.describe(self);
-            return Err(NorStorageBusError::StorageBusIoError);
+    /// Read a value of type `T` starting at `offset` through the AHB memory mapped window
+    ///
+    /// `offset` does not need to be aligned for `T`.
+    pub fn read_as<T: FromBytes>(&self, offset: u32) -> Result<T, NorStorageBusError> {
+        let mut value = core::mem::MaybeUninit::<T>::zeroed();
+
+        // SAFETY: `value` is zero initialized, has no padding and is valid for writes of `size_of::<T>()` bytes
+        let bytes =
+            unsafe { core::slice::from_raw_parts_mut(value.as_mut_ptr() as *mut u8, core::mem::size_of::<T>()) };
+        self.read_mapped(offset, bytes)?;
+
+        // SAFETY: `T: FromBytes` is valid for any bit pattern
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Program `cmd` into LUT sequence `seq_id`
+    ///
+    /// Lets a device driver register its own command table, e.g. the read and program
+    /// sequences referenced by `ard_seq_index`/`awr_seq_index` for AHB accesses. Opcode,
+    /// address width, SDR/DDR mode, dummy cycles and bus width are taken from `cmd`. Sequence 0
+    /// is reprogrammed on every IP command and cannot be used here.
+    pub fn program_sequence(&mut self, seq_id: u8, cmd: &NorStorageCmd) -> Result<(), NorStorageBusError> {
+        if seq_id == OPERATION_SEQ_NUMBER || seq_id >= LUT_SEQ_COUNT {
+            return Err(NorStorageBusError::StorageBusInternalError);
         }
 
-        num_rx_watermark_slot = self.rx_watermark / FIFO_SLOT_SIZE as u8;
+        // Catch malformed commands before they reach the LUT
+        check_cmd(cmd)?;
 
-        for watermark_sized_chunk in read_data.chunks_mut(self.rx_watermark as usize) {
-            if watermark_sized_chunk.len() < self.rx_watermark as usize {
-                #[cfg(feature = "time")]
-                {
-                    let start = Instant::now();
-                    while ((self.info.regs.iprxfsts().read().fill().bits() * 8) as u32) < size {
-                        let timedout = is_expired(start, DATA_FILL_TIMEOUT);
-                        if timedout {
-                            return Err(NorStorageBusError::StorageBusInternalError);
-                        }
-                    }
-                }
-                #[cfg(not(feature = "time"))]
-                {
-                    while ((self.info.regs.iprxfsts().read().fill().bits() * 8) as u32) < size {}
-                }
-            } else {
-                #[cfg(feature = "time")]
-                {
-                    let start = Instant::now();
-                    while self.info.regs.intr().read().iprxwa().bit_is_clear() {
-                        let timedout = is_expired(start, TX_FIFO_FREE_WATERMARK_TIMEOUT);
-                        if timedout {
-                            return Err(NorStorageBusError::StorageBusInternalError);
-                        }
-                    }
-                }
-                #[cfg(not(feature = "time"))]
-                {
-                    while self.info.regs.intr().read().iprxwa().bit_is_clear() {}
-                }
-            }
-            for (chunk, slot) in watermark_sized_chunk
-                .chunks_mut(FIFO_SLOT_SIZE as usize)
-                .zip(0..num_rx_watermark_slot)
-            {
-                let data = self.info.regs.rfdr(slot as usize).read().bits();
-                if chunk.len() < FIFO_SLOT_SIZE as usize {
-                    // We cannot do copy from slice as it will cause a panic
-                    for i in 0..chunk.len() {
-                        chunk[i] = (data >> (i * 8)) as u8;
-                    }
-                } else {
-                    chunk.copy_from_slice(&data.to_le_bytes());
-                }
-                size -= chunk.len() as u32;
-            }
-            self.info.regs.intr().modify(|_, w| w.iprxwa().clear_bit_by_one());
+        // A read-only bus never programs a modifying command into the LUT
+        if self.read_only && is_modifying_cmd(cmd) {
+            return Err(NorStorageBusError::StorageBusNotAvailable);
         }
 
+        self.check_bus_width(cmd)?;
+
+        self.program_lut(cmd, seq_id);
+
         Ok(())
     }
 
-    fn write_cmd_data(&mut self, write_data: &[u8]) -> Result<(), NorStorageBusError> {
-        // Check for any errors during the transfer
-        let error = self.check_transfer_status();
-        if let Err(e) = error {
-            e.describe(self);
-            return Err(NorStorageBusError::StorageBusIoError);
+    /// Program a raw LUT sequence, e.g. one produced by [`LutSequenceBuilder`], into slot `seq_id`
+    ///
+    /// The same slot restrictions as [`Self::program_sequence`] apply. On a read-only bus the
+    /// sequence is rejected if any of its instructions is a write data phase or a modifying
+    /// opcode.
+    pub fn program_raw_sequence(&mut self, seq_id: u8, seq: &[u32; 4]) -> Result<(), NorStorageBusError> {
+        if seq_id == OPERATION_SEQ_NUMBER || seq_id >= LUT_SEQ_COUNT {
+            return Err(NorStorageBusError::StorageBusInternalError);
         }
 
-        let num_tx_watermark_slot = self.tx_watermark / FIFO_SLOT_SIZE as u8;
-
-        for watermark_sized_chunk in write_data.chunks(self.tx_watermark as usize) {
-            // Wait for space in TX FIFO
-            #[cfg(feature = "time")]
-            {
-                let start = Instant::now();
-                while self.info.regs.intr().read().iptxwe().bit_is_clear() {
-                    let timedout = is_expired(start, TX_FIFO_FREE_WATERMARK_TIMEOUT);
-                    if timedout {
-                        return Err(NorStorageBusError::StorageBusInternalError);
-                    }
-                }
-            }
-            #[cfg(not(feature = "time"))]
-            {
-                while self.info.regs.intr().read().iptxwe().bit_is_clear() {}
-            }
-
-            for (chunk, slot) in watermark_sized_chunk
-                .chunks(FIFO_SLOT_SIZE as usize)
-                .zip(0..num_tx_watermark_slot)
-            {
-                let mut temp = 0_u32;
-                if chunk.len() < FIFO_SLOT_SIZE as usize {
-                    // We cannot do copy from slice as it will cause a panic
-                    for i in 0..chunk.len() as u32 {
-                        temp |= (chunk[i as usize] as u32) << (i * 8);
-                    }
-                } else {
-                    temp = u32::from_ne_bytes(
-                        chunk
-                            .try_into()
-                            .map_err(|_| NorStorageBusError::StorageBusInternalError)?,
-                    );
-                }
-                self.info.regs.tfdr(slot as usize).write(|w| unsafe {
-                    //SAFETY: Operation is safe as we are programming the data to be sent to the flash
-                    w.bits(temp)
-                });
-            }
-            // Clear out the water mark level data
-            self.info.regs.intr().modify(|_, w| w.iptxwe().clear_bit_by_one());
+        if self.read_only && is_modifying_seq(seq) {
+            return Err(NorStorageBusError::StorageBusNotAvailable);
         }
 
+        self.write_lut_sequence(seq_id, seq);
+
         Ok(())
     }
-}
 
-impl FlexSpiConfigurationPort {
-    /// Initialize FlexSPI
-    pub fn configure_flexspi(&mut self, config: &FlexspiConfig) -> Result<(), ()> {
+    // Store `seq` in LUT slot `seq_id`, which the caller has bounds checked
+    fn write_lut_sequence(&mut self, seq_id: u8, seq: &[u32; 4]) {
         let regs = self.info.regs;
+        regs.lutkey().modify(|_, w| unsafe { w.key().bits(LUT_UNLOCK_CODE) });
+        regs.lutcr().write(|w| w.unlock().set_bit());
 
-        // Enable Clock and deassert Reset
-        enable_and_reset::<peripherals::FLEXSPI>();
+        for (i, word) in seq.iter().enumerate() {
+            // SAFETY: any 32 bit value is a valid LUT entry, the sequence index is bounds checked by the caller
+            regs.lut((seq_id * 4) as usize + i).write(|w| unsafe { w.bits(*word) });
+        }
 
-        let sysctl_reg = unsafe { &*crate::pac::Sysctl0::ptr() };
-        sysctl_reg
-            .pdruncfg1_clr()
-            .write(|w| w.flexspi_sram_apd().clr_pdruncfg1().flexspi_sram_ppd().clr_pdruncfg1());
+        regs.lutkey().modify(|_, w| unsafe { w.key().bits(LUT_UNLOCK_CODE) });
+        regs.lutcr().modify(|_, w| w.lock().set_bit());
+    }
 
-        // These register sequence needs to be updated sequentially. Hence we dont merge the calls
-        regs.mcr0().modify(|_, w| w.swreset().set_bit());
-        #[cfg(feature = "time")]
-        {
-            let start = Instant::now();
-            while regs.mcr0().read().swreset().bit_is_set() {
-                let timedout = is_expired(start, RESET_TIMEOUT);
-                if timedout {
-                    return Err(());
-                }
-            }
-        }
-        #[cfg(not(feature = "time"))]
-        {
-            while regs.mcr0().read().swreset().bit_is_set() {}
+    /// Program a table of raw LUT sequences into consecutive slots starting at `first_seq_id`
+    ///
+    /// Lets a driver for a non-default flash install its whole command table, e.g. one kept
+    /// next to its FlexSPI configuration, in one call. Every sequence is checked as in
+    /// [`Self::program_raw_sequence`]; on error the sequences before the failing one stay
+    /// programmed.
+    pub fn program_raw_sequences(&mut self, first_seq_id: u8, seqs: &[[u32; 4]]) -> Result<(), NorStorageBusError> {
+        if first_seq_id as usize + seqs.len() > LUT_SEQ_COUNT as usize {
+            return Err(NorStorageBusError::StorageBusInternalError);
         }
 
-        //• Set MCR0[MDIS] to 0x1 (Make sure self.flexspi_ref is configured in module stop mode)
-        regs.mcr0().modify(|_, w| w.mdis().set_bit());
+        for (seq_id, seq) in (first_seq_id..).zip(seqs) {
+            self.program_raw_sequence(seq_id, seq)?;
+        }
 
-        //• Configure module control registers: MCR0, MCR1, MCR2. (Don't change MCR0[MDIS])
-        regs.mcr0().modify(|_, w| {
-            w.rxclksrc()
-                .variant(config.rx_sample_clock)
-                .dozeen()
-                .variant(config.enable_doze)
-                .sckfreerunen()
-                .variant(config.enable_sck_free_running)
-                .hsen()
-                .variant(config.enable_half_speed_access)
-        });
+        Ok(())
+    }
 
-        regs.mcr1().modify(|_, w| unsafe {
-            w.ahbbuswait()
-                .bits(config.ahb_config.ahb_bus_timeout_cycle)
-                .seqwait()
+    /// Run a sequence previously loaded with [`Self::program_raw_sequence`]
+    ///
+    /// Covers commands whose phases use different bus widths, such as the 1-1-4 and 1-4-4
+    /// quad reads of W25Q style parts, which a single `NorStorageCmd` cannot describe.
+    /// `read_buf` or `write_buf` selects the data direction and its length the data size,
+    /// at most one IP transfer (128 bytes).
+    pub fn send_sequence(
+        &mut self,
+        seq_id: u8,
+        addr: Option<u32>,
+        read_buf: Option<&mut [u8]>,
+        write_buf: Option<&[u8]>,
+    ) -> Result<(), NorStorageBusError> {
+        if seq_id == OPERATION_SEQ_NUMBER || seq_id >= LUT_SEQ_COUNT {
+            return Err(NorStorageBusError::StorageBusInternalError);
+        }
+
+        self.run_sequence(seq_id, addr, read_buf, write_buf)
+    }
+
+    /// Load `seq` into the LUT slot used for IP commands and run it once
+    ///
+    /// An escape hatch for one-off vendor commands that neither `NorStorageCmd` nor a
+    /// registered sequence covers, e.g. a security register read. The data phase is handled
+    /// as in [`Self::send_sequence`]. On a read-only bus the sequence is rejected if any of its
+    /// instructions is a write data phase or a modifying opcode, or if it has write data.
+    pub fn send_raw_sequence(
+        &mut self,
+        seq: &[u32; 4],
+        addr: Option<u32>,
+        read_buf: Option<&mut [u8]>,
+        write_buf: Option<&[u8]>,
+    ) -> Result<(), NorStorageBusError> {
+        if self.read_only && is_modifying_seq(seq) {
+            return Err(NorStorageBusError::StorageBusNotAvailable);
+        }
+
+        self.write_lut_sequence(OPERATION_SEQ_NUMBER, seq);
+        self.run_sequence(OPERATION_SEQ_NUMBER, addr, read_buf, write_buf)
+    }
+
+    fn run_sequence(
+        &mut self,
+        seq_id: u8,
+        addr: Option<u32>,
+        read_buf: Option<&mut [u8]>,
+        write_buf: Option<&[u8]>,
+    ) -> Result<(), NorStorageBusError> {
+        if self.read_only && write_buf.is_some() {
+            return Err(NorStorageBusError::StorageBusNotAvailable);
+        }
+
+        let size = match (&read_buf, &write_buf) {
+            (Some(_), Some(_)) => return Err(NorStorageBusError::StorageBusInternalError),
+            (Some(buf), None) => buf.len(),
+            (None, Some(buf)) => buf.len(),
+            (None, None) => 0,
+        };
+        if size > MAX_TRANSFER_SIZE as usize {
+            return Err(NorStorageBusError::StorageBusInternalError);
+        }
+
+        self.check_watermarks()?;
+        self.setup_ip_transfer(seq_id, addr, Some(size as u32));
+        self.execute_ip_cmd();
+        self.wait_for_cmd_completion()?;
+
+        if let Some(buf) = read_buf {
+            self.read_cmd_data(buf)?;
+        } else if let Some(buf) = write_buf {
+            self.write_cmd_data(buf)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read `read_buf.len()` bytes starting at `offset` through IP commands
+    ///
+    /// Uses the read sequence registered in slot `seq_id` with [`Self::program_sequence`] or
+    /// [`Self::program_raw_sequence`], and splits the read into IP transfers of at most 128
+    /// bytes. Unlike [`Self::read_mapped`] this does not depend on the AHB window, so it also
+    /// works for a device that is not mapped for XIP.
+    pub fn read_with_sequence(
+        &mut self,
+        seq_id: u8,
+        offset: u32,
+        read_buf: &mut [u8],
+    ) -> Result<(), NorStorageBusError> {
+        offset
+            .checked_add(read_buf.len() as u32)
+            .ok_or(NorStorageBusError::StorageBusInternalError)?;
+
+        for (i, chunk) in read_buf.chunks_mut(MAX_TRANSFER_SIZE as usize).enumerate() {
+            let addr = offset + i as u32 * MAX_TRANSFER_SIZE;
+            self.send_sequence(seq_id, Some(addr), Some(chunk), None)?;
+        }
+
+        Ok(())
+    }
+
+    /// Read `out.len()` bytes of register style data with the sequence in slot `seq_id`
+    ///
+    /// For commands with an address phase, e.g. SFDP or configuration register reads, the read
+    /// is split like [`Self::read_with_sequence`]. Without an address a command cannot be
+    /// resumed, so the read is limited to a single IP transfer (128 bytes).
+    pub fn read_register(&mut self, seq_id: u8, addr: Option<u32>, out: &mut [u8]) -> Result<(), NorStorageBusError> {
+        match addr {
+            Some(addr) => self.read_with_sequence(seq_id, addr, out),
+            None => self.send_sequence(seq_id, None, Some(out), None),
+        }
+    }
+
+    /// Calibrate the read sampling point with the controller's data learning feature
+    ///
+    /// `learn_seq` is a read sequence that ends in a LEARN instruction, e.g. built with
+    /// [`LutSequenceBuilder`], and is programmed into LUT slot `seq_id`. The flash at `addr`
+    /// must hold `pattern`, which is loaded into DLPR before the sequence runs. The controller
+    /// compares the sampled data against the pattern and latches the matching sampling point;
+    /// a failed learning run is reported as [`NorStorageBusError::StorageBusInternalError`].
+    pub fn run_data_learning(
+        &mut self,
+        seq_id: u8,
+        learn_seq: &[u32; 4],
+        addr: u32,
+        pattern: u32,
+    ) -> Result<(), NorStorageBusError> {
+        self.check_watermarks()?;
+        self.program_raw_sequence(seq_id, learn_seq)?;
+
+        // SAFETY: any 32 bit value is a valid data learning pattern
+        self.info.regs.dlpr().write(|w| unsafe { w.bits(pattern) });
+
+        self.setup_ip_transfer(seq_id, Some(addr), Some(0));
+        self.execute_ip_cmd();
+        let result = self.wait_for_cmd_completion();
+
+        // The learning read may leave sampled data behind in the RX FIFO
+        self.info.regs.iprxfcr().modify(|_, w| w.clriprxf().set_bit());
+
+        result
+    }
+
+    /// Size in bytes of the attached flash, 0 until the device port is configured
+    pub fn capacity(&self) -> usize {
+        self.configport.capacity()
+    }
+
+    /// Configure an additional chip select on the port driven by this bus
+    ///
+    /// Used for stacked packages where a second die sits behind its own chip select on the
+    /// same port. The pin must belong to the port this bus was created for. A pad that is a
+    /// chip select on both ports, such as PIO2_19, needs the port spelled out, e.g.
+    /// `config_cs_pin::<PortA, _>(p.PIO2_19)`.
+    pub fn config_cs_pin<P: FlexSpiPort, C: FlexSpiCsPin<P>>(&self, cs: Peri<'_, C>) -> Result<(), ()> {
+        if !self.on_port::<P>() {
+            return Err(());
+        }
+        cs.config_pin();
+        Ok(())
+    }
+
+    fn on_port<P: FlexSpiPort>(&self) -> bool {
+        P::PORT == self.configport.flash_port
+    }
+
+    /// Switch the configuration port to the device behind `cs`
+    ///
+    /// Subsequent calls to [`FlexSpiConfigurationPort::configure_device_port`] and
+    /// [`Self::capacity`] then apply to that device. The controller decodes the chip select
+    /// from the command address, which is laid out as A1, A2, B1, B2 in that order, so
+    /// commands for the second device start right after the first device's capacity.
+    pub fn select_device<P: FlexSpiPort, C: FlexSpiCsPin<P>>(&mut self, _cs: &Peri<'_, C>) -> Result<(), ()> {
+        if !self.on_port::<P>() {
+            return Err(());
+        }
+        self.configport.device_instance = C::DEVICE_INSTANCE;
+        Ok(())
+    }
+
+    /// Raw access to the FlexSPI register block
+    ///
+    /// Escape hatch for registers or fields the driver does not expose yet.
+    ///
+    /// # Safety
+    ///
+    /// The driver assumes it is the only owner of the controller. Writes through this reference
+    /// bypass all of its bookkeeping: changing the LUT, FIFO, port or clock configuration while a
+    /// command or AHB access is in flight, or leaving the controller in a state the driver does
+    /// not expect, can corrupt transfers or hang the bus. The caller must make sure no transfer is
+    /// in progress and that any state the driver relies on is restored afterwards.
+    pub unsafe fn regs(&self) -> &'static crate::pac::flexspi::RegisterBlock {
+        self.info.regs
+    }
+}
+
+impl<'d, M: Mode> FlexspiNorStorageBus<'d, M> {
+    fn read_data(&mut self, cmd: &NorStorageCmd, read_buf: &mut [u8]) -> Result<(), NorStorageBusError> {
+        let size = cmd.data_bytes.ok_or(NorStorageBusError::StorageBusInternalError)?;
+
+        if read_buf.len() != size as usize {
+            return Err(NorStorageBusError::StorageBusInternalError);
+        }
+
+        for chunk in read_buf.chunks_mut(MAX_TRANSFER_SIZE as usize) {
+            self.read_cmd_data(chunk)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_data(&mut self, cmd: &NorStorageCmd, write_buf: &[u8]) -> Result<(), NorStorageBusError> {
+        let size = cmd.data_bytes.ok_or(NorStorageBusError::StorageBusInternalError)?;
+
+        if write_buf.len() != size as usize {
+            return Err(NorStorageBusError::StorageBusInternalError);
+        }
+
+        for chunk in write_buf.chunks(MAX_TRANSFER_SIZE as usize) {
+            self.write_cmd_data(chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the IP command to finish.
+    ///
+    /// A command rejected or aborted by the controller never raises IPCMDDONE, so the error
+    /// flags end the wait as well and are reported as the matching `FlexSpiError`.
+    fn wait_for_cmd_completion(&mut self) -> Result<(), NorStorageBusError> {
+        #[cfg(feature = "time")]
+        {
+            let start = Instant::now();
+            while !self.cmd_finished() {
+                let timedout = is_expired(start, CMD_COMPLETION_TIMEOUT);
+                if timedout {
+                    return Err(NorStorageBusError::StorageBusIoError);
+                }
+            }
+        }
+        #[cfg(not(feature = "time"))]
+        {
+            while !self.cmd_finished() {}
+        }
+
+        self.info.regs.intr().write(|w| w.ipcmddone().clear_bit_by_one());
+
+        self.check_transfer_status().map_err(|e| {
+            e.describe(self);
+            <FlexSpiError as Into<NorStorageBusError>>::into(e)
+        })
+    }
+
+    fn cmd_finished(&self) -> bool {
+        let intr = self.info.regs.intr().read();
+
+        intr.ipcmddone().bit_is_set()
+            || intr.ipcmderr().bit_is_set()
+            || intr.ipcmdge().bit_is_set()
+            || intr.seqtimeout().bit_is_set()
+    }
+
+    /// Wait until `size` bytes (at most one watermark) can be read from the IP RX FIFO
+    fn wait_for_rx_data(&self, size: u32) -> Result<(), NorStorageBusError> {
+        if size < self.rx_watermark as u32 {
+            #[cfg(feature = "time")]
+            {
+                let start = Instant::now();
+                while ((self.info.regs.iprxfsts().read().fill().bits() * 8) as u32) < size {
+                    let timedout = is_expired(start, DATA_FILL_TIMEOUT);
+                    if timedout {
+                        return Err(NorStorageBusError::StorageBusInternalError);
+                    }
+                }
+            }
+            #[cfg(not(feature = "time"))]
+            {
+                while ((self.info.regs.iprxfsts().read().fill().bits() * 8) as u32) < size {}
+            }
+        } else {
+            #[cfg(feature = "time")]
+            {
+                let start = Instant::now();
+                while self.info.regs.intr().read().iprxwa().bit_is_clear() {
+                    let timedout = is_expired(start, TX_FIFO_FREE_WATERMARK_TIMEOUT);
+                    if timedout {
+                        return Err(NorStorageBusError::StorageBusInternalError);
+                    }
+                }
+            }
+            #[cfg(not(feature = "time"))]
+            {
+                while self.info.regs.intr().read().iprxwa().bit_is_clear() {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Wait until the IP TX FIFO has room for one watermark of data
+    fn wait_for_tx_space(&self) -> Result<(), NorStorageBusError> {
+        #[cfg(feature = "time")]
+        {
+            let start = Instant::now();
+            while self.info.regs.intr().read().iptxwe().bit_is_clear() {
+                let timedout = is_expired(start, TX_FIFO_FREE_WATERMARK_TIMEOUT);
+                if timedout {
+                    return Err(NorStorageBusError::StorageBusInternalError);
+                }
+            }
+        }
+        #[cfg(not(feature = "time"))]
+        {
+            while self.info.regs.intr().read().iptxwe().bit_is_clear() {}
+        }
+
+        Ok(())
+    }
+
+    /// Drain the IP RX FIFO into `read_data`.
+    ///
+    /// Data is pulled one watermark at a time, each RFDR slot being unpacked into four bytes,
+    /// and IPRXWA is cleared after every drain so the FIFO can refill. A trailing chunk that is
+    /// smaller than the watermark (or not a multiple of 4 bytes) is read once the fill level covers it.
+    /// Exactly `read_data.len()` bytes are written; the unused bytes of a partial last slot are
+    /// dropped and the FIFO is cleared before the next command.
+    fn read_cmd_data(&mut self, read_data: &mut [u8]) -> Result<(), NorStorageBusError> {
+        let num_rx_watermark_slot;
+
+        let error = self.check_transfer_status();
+
+        if let Err(e) = error {
+            e.describe(self);
+            return Err(NorStorageBusError::StorageBusIoError);
+        }
+
+        num_rx_watermark_slot = self.rx_watermark / FIFO_SLOT_SIZE as u8;
+
+        for watermark_sized_chunk in read_data.chunks_mut(self.rx_watermark as usize) {
+            self.wait_for_rx_data(watermark_sized_chunk.len() as u32)?;
+
+            for (chunk, slot) in watermark_sized_chunk
+                .chunks_mut(FIFO_SLOT_SIZE as usize)
+                .zip(0..num_rx_watermark_slot)
+            {
+                let data = self.info.regs.rfdr(slot as usize).read().bits();
+                chunk.copy_from_slice(&data.to_le_bytes()[..chunk.len()]);
+            }
+            self.info.regs.intr().write(|w| w.iprxwa().clear_bit_by_one());
+        }
+
+        Ok(())
+    }
+
+    fn write_cmd_data(&mut self, write_data: &[u8]) -> Result<(), NorStorageBusError> {
+        // Check for any errors during the transfer
+        let error = self.check_transfer_status();
+        if let Err(e) = error {
+            e.describe(self);
+            return Err(NorStorageBusError::StorageBusIoError);
+        }
+
+        let num_tx_watermark_slot = self.tx_watermark / FIFO_SLOT_SIZE as u8;
+
+        for watermark_sized_chunk in write_data.chunks(self.tx_watermark as usize) {
+            self.wait_for_tx_space()?;
+
+            for (chunk, slot) in watermark_sized_chunk
+                .chunks(FIFO_SLOT_SIZE as usize)
+                .zip(0..num_tx_watermark_slot)
+            {
+                let word = tx_fifo_word(chunk);
+                self.info.regs.tfdr(slot as usize).write(|w| unsafe {
+                    //SAFETY: Operation is safe as we are programming the data to be sent to the flash
+                    w.bits(word)
+                });
+            }
+            // Clear out the water mark level data
+            self.info.regs.intr().write(|w| w.iptxwe().clear_bit_by_one());
+        }
+
+        Ok(())
+    }
+}
+
+impl FlexSpiConfigurationPort {
+    /// Widest bus the data pins passed to the constructor support
+    ///
+    /// Commands are issued with their own `NorStorageCmd::bus_width`, so a single lane read-id
+    /// still works on a quad bus. A command wider than this is rejected with
+    /// `StorageBusNotAvailable`. Buses created without pins are not restricted.
+    pub fn bus_width(&self) -> FlexSpiBusWidth {
+        self.bus_width
+    }
+
+    /// Whether the arbitrator and the sequence engine are idle, i.e. no AHB or IP command is
+    /// being executed
+    pub fn is_bus_idle(&self) -> bool {
+        let sts0 = self.info.regs.sts0().read();
+        sts0.arbidle().bit_is_set() && sts0.seqidle().bit_is_set()
+    }
+
+    /// Whether an AHB read prefetch was suspended by an IP command and is still pending resume
+    pub fn is_ahb_suspended(&self) -> bool {
+        self.info.regs.ahbspndsts().read().active().bit_is_set()
+    }
+
+    /// Wait until [`Self::is_bus_idle`] holds, e.g. before handing the controller to another
+    /// device or task
+    pub fn wait_bus_idle(&self) -> Result<(), FlexspiInitError> {
+        if poll_until(IDLE_TIMEOUT, || self.is_bus_idle()) {
+            Ok(())
+        } else {
+            Err(FlexspiInitError::IdleTimeout)
+        }
+    }
+
+    /// Invalidate the AHB RX prefetch and TX buffers
+    ///
+    /// Reads through the memory mapped window may otherwise return data prefetched before the
+    /// flash was modified. Called implicitly by the bus after `write_mapped` and after any
+    /// program or erase IP command; call it after modifying the flash by other means.
+    ///
+    /// The AHB buffers can only be cleared with a software reset of the controller, so this is
+    /// [`Self::soft_reset`]: the LUT and the configuration are kept.
+    pub fn flush_ahb_buffers(&mut self) -> Result<(), FlexspiInitError> {
+        // Window accesses are plain volatile memory accesses, not register writes: finish the
+        // ones already issued before the reset, and keep later ones from being issued before
+        // SWRESET has cleared
+        cortex_m::asm::dsb();
+        self.soft_reset()?;
+        cortex_m::asm::dsb();
+
+        Ok(())
+    }
+
+    /// Software reset the controller, e.g. to recover from a hung command sequence
+    ///
+    /// SWRESET resets the sequence engines, the FIFOs and the AHB buffers but keeps the LUT and
+    /// the configuration registers, so the bus is usable again without running
+    /// [`Self::configure_flexspi`]. The module is left enabled.
+    pub fn soft_reset(&mut self) -> Result<(), FlexspiInitError> {
+        let regs = self.info.regs;
+
+        // SWRESET only runs while the module is enabled
+        regs.mcr0().modify(|_, w| w.mdis().clear_bit());
+        regs.mcr0().modify(|_, w| w.swreset().set_bit());
+        if !poll_until(RESET_TIMEOUT, || regs.mcr0().read().swreset().bit_is_clear()) {
+            return Err(FlexspiInitError::ResetTimeout);
+        }
+
+        Ok(())
+    }
+
+    /// Size in bytes of the flash attached to this port and device instance
+    ///
+    /// Derived from the FLSHSZ field (in KB) programmed by [`Self::configure_device_port`].
+    /// Returns 0 while the device port has not been configured.
+    pub fn capacity(&self) -> usize {
+        let regs = self.info.regs;
+        let size_kb = match (self.flash_port, self.device_instance) {
+            (FlexSpiFlashPort::PortA, FlexSpiFlashPortDeviceInstance::DeviceInstance0) => {
+                regs.flsha1cr0().read().flshsz().bits()
+            }
+            (FlexSpiFlashPort::PortA, FlexSpiFlashPortDeviceInstance::DeviceInstance1) => {
+                regs.flsha2cr0().read().flshsz().bits()
+            }
+            (FlexSpiFlashPort::PortB, FlexSpiFlashPortDeviceInstance::DeviceInstance0) => {
+                regs.flshb1cr0().read().flshsz().bits()
+            }
+            (FlexSpiFlashPort::PortB, FlexSpiFlashPortDeviceInstance::DeviceInstance1) => {
+                regs.flshb2cr0().read().flshsz().bits()
+            }
+        };
+
+        size_kb as usize * 1024
+    }
+
+    /// Index of this device in the per device register arrays: A1, A2, B1, B2
+    fn device_index(&self) -> usize {
+        match (self.flash_port, self.device_instance) {
+            (FlexSpiFlashPort::PortA, FlexSpiFlashPortDeviceInstance::DeviceInstance0) => 0,
+            (FlexSpiFlashPort::PortA, FlexSpiFlashPortDeviceInstance::DeviceInstance1) => 1,
+            (FlexSpiFlashPort::PortB, FlexSpiFlashPortDeviceInstance::DeviceInstance0) => 2,
+            (FlexSpiFlashPort::PortB, FlexSpiFlashPortDeviceInstance::DeviceInstance1) => 3,
+        }
+    }
+
+    // Programmed FLSHSZ of the four devices in A1, A2, B1, B2 order
+    fn device_sizes_kb(&self) -> [u32; 4] {
+        let regs = self.info.regs;
+        [
+            regs.flsha1cr0().read().flshsz().bits(),
+            regs.flsha2cr0().read().flshsz().bits(),
+            regs.flshb1cr0().read().flshsz().bits(),
+            regs.flshb2cr0().read().flshsz().bits(),
+        ]
+    }
+
+    /// Offset of this device in the AHB window, the sum of the sizes of the devices mapped before it
+    fn window_offset(&self) -> u32 {
+        let preceding = self.device_index();
+
+        self.device_sizes_kb()[..preceding]
+            .iter()
+            .fold(0_u32, |offset, size_kb| offset.wrapping_add(size_kb.wrapping_mul(1024)))
+    }
+
+    /// Size in bytes of this device's part of the AHB window, 0 before `configure_device_port`
+    pub fn mapped_size(&self) -> u32 {
+        self.device_sizes_kb()[self.device_index()].saturating_mul(1024)
+    }
+
+    /// Initialize FlexSPI
+    pub fn configure_flexspi(&mut self, config: &FlexspiConfig) -> Result<(), FlexspiInitError> {
+        let regs = self.info.regs;
+
+        if !is_valid_watermark(self.rx_watermark) || !is_valid_watermark(self.tx_watermark) {
+            return Err(FlexspiInitError::InvalidConfig);
+        }
+
+        // Enable Clock and deassert Reset
+        (self.info.enable_and_reset)();
+
+        let sysctl_reg = unsafe { &*crate::pac::Sysctl0::ptr() };
+        sysctl_reg
+            .pdruncfg1_clr()
+            .write(|w| w.flexspi_sram_apd().clr_pdruncfg1().flexspi_sram_ppd().clr_pdruncfg1());
+
+        // These register sequence needs to be updated sequentially. Hence we dont merge the calls
+        regs.mcr0().modify(|_, w| w.swreset().set_bit());
+        if !poll_until(RESET_TIMEOUT, || regs.mcr0().read().swreset().bit_is_clear()) {
+            return Err(FlexspiInitError::ResetTimeout);
+        }
+
+        //• Set MCR0[MDIS] to 0x1 (Make sure the controller is in module stop mode)
+        regs.mcr0().modify(|_, w| w.mdis().set_bit());
+
+        //• Configure module control registers: MCR0, MCR1, MCR2. (Don't change MCR0[MDIS])
+        regs.mcr0().modify(|_, w| unsafe {
+            w.rxclksrc()
+                .variant(config.rx_sample_clock)
+                .dozeen()
+                .variant(config.enable_doze)
+                .sckfreerunen()
+                .variant(config.enable_sck_free_running)
+                .hsen()
+                .variant(config.enable_half_speed_access)
+                .ipgrantwait()
+                .bits(config.ip_grant_timeout_cycle)
+                .ahbgrantwait()
+                .bits(config.ahb_config.ahb_grant_timeout_cycle)
+        });
+
+        regs.mcr1().modify(|_, w| unsafe {
+            w.ahbbuswait()
+                .bits(config.ahb_config.ahb_bus_timeout_cycle)
+                .seqwait()
                 .bits(config.seq_timeout_cycle)
         });
 
-        regs.mcr2().modify(|_, w| unsafe {
-            w.samedeviceen()
-                .variant(config.enable_same_config_for_all)
-                .resumewait()
-                .bits(config.ahb_config.resume_wait_cycle)
-                .sckbdiffopt()
-                .variant(config.enable_sck_b_diff_opt)
-                .clrahbbufopt()
-                .variant(config.ahb_config.enable_clear_ahb_buffer_opt)
-        });
+        regs.mcr2().modify(|_, w| unsafe {
+            w.samedeviceen()
+                .variant(config.enable_same_config_for_all)
+                .resumewait()
+                .bits(config.ahb_config.resume_wait_cycle)
+                .sckbdiffopt()
+                .variant(config.enable_sck_b_diff_opt)
+                .clrahbbufopt()
+                .variant(config.ahb_config.enable_clear_ahb_buffer_opt)
+        });
+
+        regs.ahbcr().modify(|_, w| {
+            w.readaddropt()
+                .variant(config.ahb_config.enable_read_address_opt)
+                .bufferableen()
+                .variant(config.ahb_config.enable_ahb_bufferable)
+                .cachableen()
+                .variant(config.ahb_config.enable_ahb_cachable)
+        });
+
+        regs.ahbcr()
+            .modify(|_, w| w.prefetchen().variant(config.ahb_config.enable_ahb_prefetch));
+
+        configure_ahb_rx_buffers!(regs, config.ahb_config.buffer, 0, 1, 2, 3, 4, 5, 6, 7);
+
+        // • Initialize Flash control registers (FLSHxCR0,FLSHxCR1,FLSHxCR2)
+        match (self.flash_port, self.device_instance) {
+            (FlexSpiFlashPort::PortA, FlexSpiFlashPortDeviceInstance::DeviceInstance0) => {
+                regs.flsha1cr0().modify(|_, w| unsafe { w.flshsz().bits(0) });
+            }
+            (FlexSpiFlashPort::PortA, FlexSpiFlashPortDeviceInstance::DeviceInstance1) => {
+                regs.flsha2cr0().modify(|_, w| unsafe { w.flshsz().bits(0) });
+            }
+            (FlexSpiFlashPort::PortB, FlexSpiFlashPortDeviceInstance::DeviceInstance0) => {
+                regs.flshb1cr0().modify(|_, w| unsafe { w.flshsz().bits(0) });
+            }
+            (FlexSpiFlashPort::PortB, FlexSpiFlashPortDeviceInstance::DeviceInstance1) => {
+                regs.flshb2cr0().modify(|_, w| unsafe { w.flshsz().bits(0) });
+            }
+        }
+
+        // Watermarks are given in bytes and encoded in 64 bit units minus one
+        regs.iprxfcr()
+            .modify(|_, w| unsafe { w.rxwmrk().bits((self.rx_watermark / 8) - 1) });
+        regs.iptxfcr()
+            .modify(|_, w| unsafe { w.txwmrk().bits((self.tx_watermark / 8) - 1) });
+
+        Ok(())
+    }
+
+    /// Configure the flash device port based on the external flash device
+    pub fn configure_device_port(
+        &mut self,
+        device_config: &FlexspiDeviceConfig,
+        flexspi_config: &FlexspiConfig,
+    ) -> Result<(), FlexspiInitError> {
+        let regs = self.info.regs;
+
+        if device_config.flash_size_kb > FLSHSZ_MAX_KB {
+            return Err(FlexspiInitError::InvalidConfig);
+        }
+
+        let inst = match self.device_instance {
+            FlexSpiFlashPortDeviceInstance::DeviceInstance0 => 0,
+            FlexSpiFlashPortDeviceInstance::DeviceInstance1 => 1,
+        };
+
+        self.wait_bus_idle()?;
+
+        // With a loopback sample clock (or SCK2) the read strobe is generated internally and the
+        // DLL has nothing to lock onto: use one fixed delay cell, as FLEXSPI_CalculateDll does
+        let is_unified_config = match flexspi_config.rx_sample_clock {
+            Rxclksrc::Rxclksrc0 | Rxclksrc::Rxclksrc1 => true,
+            Rxclksrc::Rxclksrc3 => device_config.is_sck2_enabled,
+        };
+
+        if is_unified_config {
+            regs.dllcr(inst).write(|w| unsafe { w.bits(DLLCR_UNIFIED_CONFIG) });
+        } else if device_config.flexspi_root_clk >= CLOCK_100MHZ {
+            // Let the DLL lock onto the DQS read strobe: DLLEN = 1, SLVDLYTARGET = 0xF
+            regs.dllcr(inst)
+                .write(|w| unsafe { w.ovrden().clear_bit().dllen().set_bit().slvdlytarget().bits(0xF) });
+        } else {
+            // Below 100MHz the DLL cannot lock, so override the delay with enough delay cells to
+            // cover the data valid time of the device
+            let valid_time_ps = device_config.data_valid_time as u32 * 1000;
+            let dll_value = valid_time_ps.div_ceil(DELAYCELLUNIT).min(DLL_OVRDVAL_MAX);
+            regs.dllcr(inst)
+                .write(|w| unsafe { w.dllen().clear_bit().ovrden().set_bit().ovrdval().bits(dll_value as u8) });
+        }
+
+        regs.flshcr4().modify(|_, w| match self.flash_port {
+            FlexSpiFlashPort::PortA => w.wmena().variant(device_config.enable_write_mask_port_a),
+            FlexSpiFlashPort::PortB => w.wmenb().variant(device_config.enable_write_mask_port_b),
+        });
+
+        match self.flash_port {
+            FlexSpiFlashPort::PortA => self.configure_flexspi_device_port_a(device_config)?,
+            FlexSpiFlashPort::PortB => self.configure_flexspi_device_port_b(device_config)?,
+        }
+
+        // Enable the module
+        regs.mcr0().modify(|_, w| w.mdis().clear_bit());
+
+        // The DLL only starts locking once the module is enabled. With the delay overridden
+        // (OVRDEN) there is no lock to wait for.
+        if regs.dllcr(inst).read().dllen().bit_is_set() {
+            let locked = poll_until(DLL_LOCK_TIMEOUT, || {
+                let sts2 = regs.sts2().read();
+                match self.flash_port {
+                    FlexSpiFlashPort::PortA => sts2.aslvlock().bit_is_set() && sts2.areflock().bit_is_set(),
+                    FlexSpiFlashPort::PortB => sts2.bslvlock().bit_is_set() && sts2.breflock().bit_is_set(),
+                }
+            });
+            if !locked {
+                return Err(FlexspiInitError::DllLockTimeout);
+            }
+            // ERR011377: the lock bits can assert before the DLL has settled
+            delay_loop_clocks(DLL_LOCK_SETTLE_US, CORE_CLOCK_MAX_HZ);
+        }
+
+        Ok(())
+    }
+
+    fn configure_flexspi_device_port_a(&self, device_config: &FlexspiDeviceConfig) -> Result<(), FlexspiInitError> {
+        let regs = self.info.regs;
+        let flash_size = device_config.flash_size_kb;
+
+        match self.device_instance {
+            FlexSpiFlashPortDeviceInstance::DeviceInstance0 => {
+                configure_ports_a!(1, regs, device_config, flash_size, self.read_only);
+            }
+
+            FlexSpiFlashPortDeviceInstance::DeviceInstance1 => {
+                configure_ports_a!(2, regs, device_config, flash_size, self.read_only);
+            }
+        }
+        Ok(())
+    }
+
+    fn configure_flexspi_device_port_b(&self, device_config: &FlexspiDeviceConfig) -> Result<(), FlexspiInitError> {
+        let regs = self.info.regs;
+        let flash_size = device_config.flash_size_kb;
+
+        match self.device_instance {
+            FlexSpiFlashPortDeviceInstance::DeviceInstance0 => {
+                configure_ports_b!(1, regs, device_config, flash_size, self.read_only);
+            }
+            FlexSpiFlashPortDeviceInstance::DeviceInstance1 => {
+                configure_ports_b!(2, regs, device_config, flash_size, self.read_only);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'d> FlexspiNorStorageBus<'d, Async> {
+    /// Send a command to the flash device, waiting for the IP command completion interrupt
+    /// instead of spinning on IPCMDDONE
+    pub async fn send_command(
+        &mut self,
+        cmd: NorStorageCmd,
+        read_buf: Option<&mut [u8]>,
+        write_buf: Option<&[u8]>,
+    ) -> Result<(), NorStorageBusError> {
+        self.execute_command(&cmd, read_buf, write_buf).await
+    }
+
+    /// Wait for an error on an AHB (memory mapped) access to the flash
+    ///
+    /// AHB reads and writes cannot fail towards the CPU, the controller only flags the error.
+    /// While this future is pending the interrupt handler latches and clears those flags, so
+    /// they no longer show up in the result of a later IP command.
+    pub async fn wait_for_ahb_error(&self) -> NorStorageBusError {
+        let info = &self.info;
+
+        let errors = poll_fn(|cx| {
+            info.state.ahb_waker.register(cx.waker());
+
+            let errors = info.state.ahb_errors.swap(0, Ordering::Relaxed);
+            if errors != 0 {
+                return Poll::Ready(errors);
+            }
+
+            info.regs.inten().modify(|_, w| {
+                w.ahbcmderren()
+                    .set_bit()
+                    .ahbcmdgeen()
+                    .set_bit()
+                    .ahbbustimeouten()
+                    .set_bit()
+            });
+            Poll::Pending
+        })
+        .await;
+
+        let result = CmdResult {
+            AhbReadCmdErr: true,
+            AhbWriteCmdErr: true,
+            IpCmdErr: false,
+        };
+        let err = if errors & AHB_ERR_GRANT != 0 {
+            FlexSpiError::CmdGrantErr { result }
+        } else if errors & AHB_ERR_BUS_TIMEOUT != 0 {
+            FlexSpiError::AhbBusTimeout { result }
+        } else {
+            FlexSpiError::CmdCheckErr { result }
+        };
+        err.into()
+    }
+
+    /// Poll the flash status register with `status_cmd` until all bits in `busy_mask` are clear
+    ///
+    /// Each status read completes on the IP command done interrupt and the task sleeps for
+    /// `poll_interval` between reads, so a long erase or program costs one wakeup per interval.
+    #[cfg(feature = "time")]
+    pub async fn wait_until_ready(
+        &mut self,
+        status_cmd: &NorStorageCmd,
+        busy_mask: u8,
+        poll_interval: Duration,
+    ) -> Result<(), NorStorageBusError> {
+        let mut status = [0_u8; FIFO_SLOT_SIZE as usize];
+        let len = status_cmd.data_bytes.unwrap_or(1).clamp(1, FIFO_SLOT_SIZE) as usize;
+
+        loop {
+            self.execute_command(status_cmd, Some(&mut status[..len]), None).await?;
+
+            if status[0] & busy_mask == 0 {
+                return Ok(());
+            }
+
+            Timer::after(poll_interval).await;
+        }
+    }
+
+    /// Suspend the erase running in the flash, see the blocking `erase_suspend`
+    pub async fn erase_suspend(&mut self) -> Result<(), NorStorageBusError> {
+        if self.erase_suspended {
+            return Ok(());
+        }
+
+        self.execute_command(&opcode_only_cmd(ERASE_SUSPEND_OPCODE), None, None)
+            .await?;
+        self.erase_suspended = true;
+
+        Ok(())
+    }
+
+    /// Resume an erase suspended with [`Self::erase_suspend`]
+    pub async fn erase_resume(&mut self) -> Result<(), NorStorageBusError> {
+        if !self.erase_suspended {
+            return Ok(());
+        }
+
+        self.execute_command(&opcode_only_cmd(ERASE_RESUME_OPCODE), None, None)
+            .await?;
+        self.erase_suspended = false;
+
+        Ok(())
+    }
+
+    /// Switch the flash between 3 and 4 byte addressing, see the blocking
+    /// `set_4byte_address_mode`
+    pub async fn set_4byte_address_mode(&mut self, enable: bool) -> Result<(), NorStorageBusError> {
+        let opcode = if enable {
+            ENTER_4BYTE_ADDR_OPCODE
+        } else {
+            EXIT_4BYTE_ADDR_OPCODE
+        };
+
+        self.execute_command(&opcode_only_cmd(opcode), None, None).await
+    }
+
+    async fn execute_command(
+        &mut self,
+        cmd: &NorStorageCmd,
+        read_buf: Option<&mut [u8]>,
+        write_buf: Option<&[u8]>,
+    ) -> Result<(), NorStorageBusError> {
+        // Catch malformed commands before they reach the LUT
+        check_cmd(cmd)?;
+
+        // A read-only bus never programs a modifying command into the LUT
+        if self.read_only && is_modifying_cmd(cmd) {
+            return Err(NorStorageBusError::StorageBusNotAvailable);
+        }
+
+        self.check_bus_width(cmd)?;
+
+        self.check_watermarks()?;
+        self.setup_ip_transfer(OPERATION_SEQ_NUMBER, cmd.addr, cmd.data_bytes);
+        self.program_lut(cmd, OPERATION_SEQ_NUMBER as u8);
+
+        // Drop any completion left over from a previous command before triggering this one
+        self.info.regs.intr().write(|w| w.ipcmddone().clear_bit_by_one());
+        self.execute_ip_cmd();
+
+        self.wait_for_cmd_completion_async().await;
+
+        self.check_transfer_status().map_err(|e| {
+            e.describe(self);
+            <FlexSpiError as Into<NorStorageBusError>>::into(e)
+        })?;
+
+        if let Some(data_cmd) = &cmd.cmdtype {
+            match data_cmd {
+                NorStorageCmdType::Read => {
+                    let buffer = read_buf.ok_or(NorStorageBusError::StorageBusInternalError)?;
+                    self.read_data_dma(cmd, buffer).await?;
+                }
+                NorStorageCmdType::Write => {
+                    let buffer = write_buf.ok_or(NorStorageBusError::StorageBusInternalError)?;
+                    self.write_data_dma(cmd, buffer).await?;
+                }
+            }
+        }
+
+        // Prefetched AHB data may predate the program or erase just issued
+        if is_modifying_cmd(cmd) {
+            self.flush_ahb_buffers()?;
+        }
 
-        regs.ahbcr().modify(|_, w| {
-            w.readaddropt()
-                .variant(config.ahb_config.enable_read_address_opt)
-                .bufferableen()
-                .variant(config.ahb_config.enable_ahb_bufferable)
-                .cachableen()
-                .variant(config.ahb_config.enable_ahb_cachable)
-        });
+        Ok(())
+    }
 
-        regs.ahbcr()
-            .modify(|_, w| w.prefetchen().variant(config.ahb_config.enable_ahb_prefetch));
+    async fn read_data_dma(&mut self, cmd: &NorStorageCmd, read_buf: &mut [u8]) -> Result<(), NorStorageBusError> {
+        let size = cmd.data_bytes.ok_or(NorStorageBusError::StorageBusInternalError)?;
 
-        regs.ahbrxbuf0cr0().modify(|_, w| unsafe {
-            w.mstrid()
-                .bits(0)
-                .prefetchen()
-                .set_bit()
-                .bufsz()
-                .bits(256)
-                .priority()
-                .bits(0)
-        });
+        if read_buf.len() != size as usize {
+            return Err(NorStorageBusError::StorageBusInternalError);
+        }
 
-        regs.ahbrxbuf1cr0().modify(|_, w| unsafe {
-            w.mstrid()
-                .bits(0)
-                .prefetchen()
-                .set_bit()
-                .bufsz()
-                .bits(256)
-                .priority()
-                .bits(0)
-        });
+        for chunk in read_buf.chunks_mut(MAX_TRANSFER_SIZE as usize) {
+            self.read_cmd_data_dma(chunk).await?;
+        }
 
-        regs.ahbrxbuf2cr0().modify(|_, w| unsafe {
-            w.mstrid()
-                .bits(0)
-                .prefetchen()
-                .set_bit()
-                .bufsz()
-                .bits(256)
-                .priority()
-                .bits(0)
-        });
+        Ok(())
+    }
 
-        regs.ahbrxbuf3cr0().modify(|_, w| unsafe {
-            w.mstrid()
-                .bits(0)
-                .prefetchen()
-                .set_bit()
-                .bufsz()
-                .bits(256)
-                .priority()
-                .bits(0)
-        });
+    async fn write_data_dma(&mut self, cmd: &NorStorageCmd, write_buf: &[u8]) -> Result<(), NorStorageBusError> {
+        let size = cmd.data_bytes.ok_or(NorStorageBusError::StorageBusInternalError)?;
 
-        regs.ahbrxbuf4cr0().modify(|_, w| unsafe {
-            w.mstrid()
-                .bits(0)
-                .prefetchen()
-                .set_bit()
-                .bufsz()
-                .bits(256)
-                .priority()
-                .bits(0)
-        });
+        if write_buf.len() != size as usize {
+            return Err(NorStorageBusError::StorageBusInternalError);
+        }
 
-        regs.ahbrxbuf5cr0().modify(|_, w| unsafe {
-            w.mstrid()
-                .bits(0)
-                .prefetchen()
-                .set_bit()
-                .bufsz()
-                .bits(256)
-                .priority()
-                .bits(0)
-        });
+        for chunk in write_buf.chunks(MAX_TRANSFER_SIZE as usize) {
+            self.write_cmd_data_dma(chunk).await?;
+        }
 
-        regs.ahbrxbuf6cr0().modify(|_, w| unsafe {
-            w.mstrid()
-                .bits(0)
-                .prefetchen()
-                .set_bit()
-                .bufsz()
-                .bits(256)
-                .priority()
-                .bits(0)
-        });
+        Ok(())
+    }
 
-        regs.ahbrxbuf7cr0().modify(|_, w| unsafe {
-            w.mstrid()
-                .bits(0)
-                .prefetchen()
-                .set_bit()
-                .bufsz()
-                .bits(256)
-                .priority()
-                .bits(0)
-        });
+    /// Drain the IP RX FIFO into `read_data` through the RX DMA channel.
+    ///
+    /// With RXDMAEN set the controller raises its DMA request whenever a watermark of data is
+    /// available and pops it once the DMA has read the RFDR window, so every whole watermark is
+    /// one peripheral paced transfer the task sleeps on. A trailing chunk smaller than the
+    /// watermark never raises the request and is drained by `read_cmd_data`.
+    async fn read_cmd_data_dma(&mut self, read_data: &mut [u8]) -> Result<(), NorStorageBusError> {
+        if let Err(e) = self.check_transfer_status() {
+            e.describe(self);
+            return Err(NorStorageBusError::StorageBusIoError);
+        }
 
-        // • Initialize Flash control registers (FLSHxCR0,FLSHxCR1,FLSHxCR2)
-        match (self.flash_port, self.device_instance) {
-            (FlexSpiFlashPort::PortA, FlexSpiFlashPortDeviceInstance::DeviceInstance0) => {
-                regs.flsha1cr0().modify(|_, w| unsafe { w.flshsz().bits(0) });
-            }
-            (FlexSpiFlashPort::PortA, FlexSpiFlashPortDeviceInstance::DeviceInstance1) => {
-                regs.flsha2cr0().modify(|_, w| unsafe { w.flshsz().bits(0) });
-            }
-            (FlexSpiFlashPort::PortB, FlexSpiFlashPortDeviceInstance::DeviceInstance0) => {
-                regs.flshb1cr0().modify(|_, w| unsafe { w.flshsz().bits(0) });
-            }
-            (FlexSpiFlashPort::PortB, FlexSpiFlashPortDeviceInstance::DeviceInstance1) => {
-                regs.flshb2cr0().modify(|_, w| unsafe { w.flshsz().bits(0) });
+        let watermark = self.rx_watermark as usize;
+        let (dma_data, rest) = read_data.split_at_mut(read_data.len() - read_data.len() % watermark);
+
+        if !dma_data.is_empty() {
+            let regs = self.info.regs;
+            let channel = self
+                .rx_dma
+                .as_ref()
+                .ok_or(NorStorageBusError::StorageBusInternalError)?;
+            let rfdr = regs.rfdr(0).as_ptr() as *const u8;
+            let options = dma::transfer::TransferOptions {
+                width: dma::transfer::Width::Bit32,
+                peri_addr_increment: true,
+                ..Default::default()
+            };
+            // Word aligned, unlike the caller's buffer
+            let mut staging = FifoBuffer([0; MAX_TRANSFER_SIZE as usize]);
+
+            regs.iprxfcr().modify(|_, w| w.rxdmaen().set_bit());
+            // Hand the FIFO back to the processor even if the future is dropped
+            let _dma_guard = OnDrop::new(|| {
+                regs.iprxfcr().modify(|_, w| w.rxdmaen().clear_bit());
+            });
+
+            for chunk in dma_data.chunks_mut(watermark) {
+                channel
+                    .read_from_peripheral(rfdr, &mut staging.0[..watermark], options)
+                    .await;
+                chunk.copy_from_slice(&staging.0[..watermark]);
             }
         }
 
-        regs.iprxfcr().modify(|_, w| unsafe { w.rxwmrk().bits(0) });
-        regs.iptxfcr().modify(|_, w| unsafe { w.txwmrk().bits(0) });
+        if !rest.is_empty() {
+            self.read_cmd_data(rest)?;
+        }
 
         Ok(())
     }
 
-    /// Configure the flash self.flexspi_ref based on the external flash device
-    pub fn configure_device_port(
-        &self,
-        device_config: &FlexspiDeviceConfig,
-        flexspi_config: &FlexspiConfig,
-    ) -> Result<(), ()> {
-        let regs = self.info.regs;
-        let inst = match self.device_instance {
-            FlexSpiFlashPortDeviceInstance::DeviceInstance0 => 0,
-            FlexSpiFlashPortDeviceInstance::DeviceInstance1 => 1,
-        };
+    /// Fill the IP TX FIFO from `write_data` through the TX DMA channel.
+    ///
+    /// With TXDMAEN set the controller raises its DMA request whenever a watermark of FIFO space
+    /// is free and pushes the data once the DMA has filled the TFDR window. A trailing chunk
+    /// smaller than the watermark is written by `write_cmd_data`, which zero pads it.
+    async fn write_cmd_data_dma(&mut self, write_data: &[u8]) -> Result<(), NorStorageBusError> {
+        if let Err(e) = self.check_transfer_status() {
+            e.describe(self);
+            return Err(NorStorageBusError::StorageBusIoError);
+        }
 
-        #[cfg(feature = "time")]
-        {
-            let start = Instant::now();
+        let watermark = self.tx_watermark as usize;
+        let (dma_data, rest) = write_data.split_at(write_data.len() - write_data.len() % watermark);
+
+        if !dma_data.is_empty() {
+            let regs = self.info.regs;
+            let channel = self
+                .tx_dma
+                .as_ref()
+                .ok_or(NorStorageBusError::StorageBusInternalError)?;
+            let tfdr = regs.tfdr(0).as_ptr() as *mut u8;
+            let options = dma::transfer::TransferOptions {
+                width: dma::transfer::Width::Bit32,
+                peri_addr_increment: true,
+                ..Default::default()
+            };
+            // Word aligned, unlike the caller's buffer
+            let mut staging = FifoBuffer([0; MAX_TRANSFER_SIZE as usize]);
+
+            regs.iptxfcr().modify(|_, w| w.txdmaen().set_bit());
+            // Hand the FIFO back to the processor even if the future is dropped
+            let _dma_guard = OnDrop::new(|| {
+                regs.iptxfcr().modify(|_, w| w.txdmaen().clear_bit());
+            });
 
-            while !(regs.sts0().read().arbidle().bit_is_set() && regs.sts0().read().seqidle().bit_is_set()) {
-                let timedout = is_expired(start, IDLE_TIMEOUT);
-                if timedout {
-                    return Err(());
-                }
+            for chunk in dma_data.chunks(watermark) {
+                staging.0[..watermark].copy_from_slice(chunk);
+                channel
+                    .write_to_peripheral(&staging.0[..watermark], tfdr, options)
+                    .await;
             }
         }
-        #[cfg(not(feature = "time"))]
-        {
-            while !(regs.sts0().read().arbidle().bit_is_set() && regs.sts0().read().seqidle().bit_is_set()) {}
+
+        if !rest.is_empty() {
+            self.write_cmd_data(rest)?;
         }
 
-        regs.dllcr(inst).modify(|_, w| {
-            let is_unified_config;
-            let mut dll_value;
-            let temp;
+        Ok(())
+    }
 
-            let rx_sample_clock = flexspi_config.rx_sample_clock;
-            match rx_sample_clock {
-                Rxclksrc::Rxclksrc0 => {
-                    is_unified_config = true;
-                }
-                Rxclksrc::Rxclksrc1 => {
-                    is_unified_config = true;
-                }
-                Rxclksrc::Rxclksrc3 => {
-                    is_unified_config = device_config.is_sck2_enabled;
-                }
-            }
-            w.ovrden().variant(is_unified_config);
-            if device_config.flexspi_root_clk >= CLOCK_100MHZ {
-                /* DLLEN = 1, SLVDLYTARGET = 0xF, */
-                unsafe {
-                    w.slvdlytarget().bits(0xF).dllen().set_bit();
-                }
-            } else {
-                temp = (device_config.data_valid_time) as u32 * 1000; /* Convert data valid time in ns to ps. */
-                dll_value = temp / DELAYCELLUNIT as u32;
-                if dll_value * (DELAYCELLUNIT as u32) < temp {
-                    dll_value += 1;
-                }
-                unsafe {
-                    w.ovrdval().bits((dll_value) as u8);
-                }
+    async fn wait_for_cmd_completion_async(&mut self) {
+        poll_fn(|cx| {
+            // Register waker before checking the status so the interrupt cannot be missed
+            self.info.state.waker.register(cx.waker());
+
+            if self.cmd_finished() {
+                self.info.regs.intr().write(|w| w.ipcmddone().clear_bit_by_one());
+                return Poll::Ready(());
             }
-            w
-        });
 
-        regs.flshcr4().modify(|_, w| match self.flash_port {
-            FlexSpiFlashPort::PortA => w.wmena().variant(device_config.enable_write_mask_port_a),
-            FlexSpiFlashPort::PortB => w.wmenb().variant(device_config.enable_write_mask_port_b),
-        });
+            self.info.regs.inten().modify(|_, w| {
+                w.ipcmddoneen()
+                    .set_bit()
+                    .ipcmderren()
+                    .set_bit()
+                    .ipcmdgeen()
+                    .set_bit()
+                    .seqtimeouten()
+                    .set_bit()
+            });
+            Poll::Pending
+        })
+        .await
+    }
 
-        match self.flash_port {
-            FlexSpiFlashPort::PortA => self.configure_flexspi_device_port_a(device_config)?,
-            FlexSpiFlashPort::PortB => self.configure_flexspi_device_port_b(device_config)?,
+    /// Create a new FlexSPI instance in async mode with single configuration
+    pub fn new_async_single_config<T: Instance, P: FlexSpiPort, C: FlexSpiCsPin<P>>(
+        _inst: Peri<'d, T>,
+        data0: Peri<'d, impl FlexSpiPin<P>>,
+        data1: Peri<'d, impl FlexSpiPin<P>>,
+        clk: Peri<'d, impl FlexSpiPin<P>>,
+        cs: Peri<'d, C>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        config: FlexspiConfigPortData,
+        rx_dma: Peri<'d, impl RxDma<T>>,
+        tx_dma: Peri<'d, impl TxDma<T>>,
+    ) -> Self {
+        // Configure the pins
+        data0.config_pin();
+        data1.config_pin();
+        clk.config_pin();
+        cs.config_pin();
+
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Self {
+            info: T::info(),
+            _mode: core::marker::PhantomData,
+            configport: FlexSpiConfigurationPort {
+                info: T::info(),
+                device_instance: C::DEVICE_INSTANCE,
+                flash_port: P::PORT,
+                bus_width: FlexSpiBusWidth::Single,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
+            },
+            rx_watermark: config.rx_watermark,
+            tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: dma::Dma::reserve_channel(rx_dma),
+            tx_dma: dma::Dma::reserve_channel(tx_dma),
+            erase_suspended: false,
+            phantom: core::marker::PhantomData,
         }
+    }
 
-        // Enable the module
-        regs.mcr0().modify(|_, w| w.mdis().clear_bit());
+    /// Create a new FlexSPI instance in async mode with Dual configuration
+    pub fn new_async_dual_config<T: Instance, P: FlexSpiPort, C: FlexSpiCsPin<P>>(
+        _inst: Peri<'d, T>,
+        data0: Peri<'d, impl FlexSpiPin<P>>,
+        data1: Peri<'d, impl FlexSpiPin<P>>,
+        clk: Peri<'d, impl FlexSpiPin<P>>,
+        cs: Peri<'d, C>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        config: FlexspiConfigPortData,
+        rx_dma: Peri<'d, impl RxDma<T>>,
+        tx_dma: Peri<'d, impl TxDma<T>>,
+    ) -> Self {
+        // Configure the pins
+        data0.config_pin();
+        data1.config_pin();
+        clk.config_pin();
+        cs.config_pin();
 
-        Ok(())
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Self {
+            info: T::info(),
+            _mode: core::marker::PhantomData,
+            configport: FlexSpiConfigurationPort {
+                info: T::info(),
+                device_instance: C::DEVICE_INSTANCE,
+                flash_port: P::PORT,
+                bus_width: FlexSpiBusWidth::Dual,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
+            },
+            rx_watermark: config.rx_watermark,
+            tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: dma::Dma::reserve_channel(rx_dma),
+            tx_dma: dma::Dma::reserve_channel(tx_dma),
+            erase_suspended: false,
+            phantom: core::marker::PhantomData,
+        }
     }
 
-    fn configure_flexspi_device_port_a(&self, device_config: &FlexspiDeviceConfig) -> Result<(), ()> {
-        let regs = self.info.regs;
-        let flash_size = device_config.flash_size_kb;
+    /// Create a new FlexSPI instance in async mode with Quad configuration
+    pub fn new_async_quad_config<T: Instance, P: FlexSpiPort, C: FlexSpiCsPin<P>>(
+        _inst: Peri<'d, T>,
+        data0: Peri<'d, impl FlexSpiPin<P>>,
+        data1: Peri<'d, impl FlexSpiPin<P>>,
+        data2: Peri<'d, impl FlexSpiPin<P>>,
+        data3: Peri<'d, impl FlexSpiPin<P>>,
+        clk: Peri<'d, impl FlexSpiPin<P>>,
+        cs: Peri<'d, C>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        config: FlexspiConfigPortData,
+        rx_dma: Peri<'d, impl RxDma<T>>,
+        tx_dma: Peri<'d, impl TxDma<T>>,
+    ) -> Self {
+        // Configure the pins
+        data0.config_pin();
+        data1.config_pin();
+        data2.config_pin();
+        data3.config_pin();
+        clk.config_pin();
+        cs.config_pin();
 
-        match self.device_instance {
-            FlexSpiFlashPortDeviceInstance::DeviceInstance0 => {
-                configure_ports_a!(1, regs, device_config, flash_size);
-            }
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
 
-            FlexSpiFlashPortDeviceInstance::DeviceInstance1 => {
-                configure_ports_a!(2, regs, device_config, flash_size);
-            }
+        Self {
+            info: T::info(),
+            _mode: core::marker::PhantomData,
+            configport: FlexSpiConfigurationPort {
+                info: T::info(),
+                device_instance: C::DEVICE_INSTANCE,
+                flash_port: P::PORT,
+                bus_width: FlexSpiBusWidth::Quad,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
+            },
+            rx_watermark: config.rx_watermark,
+            tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: dma::Dma::reserve_channel(rx_dma),
+            tx_dma: dma::Dma::reserve_channel(tx_dma),
+            erase_suspended: false,
+            phantom: core::marker::PhantomData,
         }
-        Ok(())
     }
 
-    fn configure_flexspi_device_port_b(&self, device_config: &FlexspiDeviceConfig) -> Result<(), ()> {
-        let regs = self.info.regs;
-        let flash_size = device_config.flash_size_kb;
+    /// Create a new FlexSPI instance in async mode with octal configuration
+    ///
+    /// `data4`-`data7` are the DATA4-7 pins of the same port.
+    pub fn new_async_octal_config<T: Instance, P: FlexSpiPort, C: FlexSpiCsPin<P>>(
+        _inst: Peri<'d, T>,
+        data0: Peri<'d, impl FlexSpiPin<P>>,
+        data1: Peri<'d, impl FlexSpiPin<P>>,
+        data2: Peri<'d, impl FlexSpiPin<P>>,
+        data3: Peri<'d, impl FlexSpiPin<P>>,
+        data4: Peri<'d, impl FlexSpiPin<P>>,
+        data5: Peri<'d, impl FlexSpiPin<P>>,
+        data6: Peri<'d, impl FlexSpiPin<P>>,
+        data7: Peri<'d, impl FlexSpiPin<P>>,
+        clk: Peri<'d, impl FlexSpiPin<P>>,
+        cs: Peri<'d, C>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        config: FlexspiConfigPortData,
+        rx_dma: Peri<'d, impl RxDma<T>>,
+        tx_dma: Peri<'d, impl TxDma<T>>,
+    ) -> Self {
+        // Configure the pins
+        data0.config_pin();
+        data1.config_pin();
+        data2.config_pin();
+        data3.config_pin();
+        data4.config_pin();
+        data5.config_pin();
+        data6.config_pin();
+        data7.config_pin();
+        clk.config_pin();
+        cs.config_pin();
 
-        match self.device_instance {
-            FlexSpiFlashPortDeviceInstance::DeviceInstance0 => {
-                configure_ports_b!(1, regs, device_config, flash_size);
-            }
-            FlexSpiFlashPortDeviceInstance::DeviceInstance1 => {
-                configure_ports_b!(2, regs, device_config, flash_size);
-            }
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Self {
+            info: T::info(),
+            _mode: core::marker::PhantomData,
+            configport: FlexSpiConfigurationPort {
+                info: T::info(),
+                device_instance: C::DEVICE_INSTANCE,
+                flash_port: P::PORT,
+                bus_width: FlexSpiBusWidth::Octal,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
+            },
+            rx_watermark: config.rx_watermark,
+            tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: dma::Dma::reserve_channel(rx_dma),
+            tx_dma: dma::Dma::reserve_channel(tx_dma),
+            erase_suspended: false,
+            phantom: core::marker::PhantomData,
+        }
+    }
+
+    /// Create a new FlexSPI instance in async mode without pin configuration
+    pub fn new_async_no_pin_config<T: Instance>(
+        _inst: Peri<'d, T>,
+        _irq: impl interrupt::typelevel::Binding<T::Interrupt, InterruptHandler<T>> + 'd,
+        config: FlexspiConfigPortData,
+        rx_dma: Peri<'d, impl RxDma<T>>,
+        tx_dma: Peri<'d, impl TxDma<T>>,
+    ) -> Self {
+        T::Interrupt::unpend();
+        unsafe { T::Interrupt::enable() };
+
+        Self {
+            info: T::info(),
+            _mode: core::marker::PhantomData,
+            configport: FlexSpiConfigurationPort {
+                info: T::info(),
+                device_instance: config.dev_instance,
+                flash_port: config.port,
+                bus_width: FlexSpiBusWidth::Octal,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
+            },
+            rx_watermark: config.rx_watermark,
+            tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: dma::Dma::reserve_channel(rx_dma),
+            tx_dma: dma::Dma::reserve_channel(tx_dma),
+            erase_suspended: false,
+            phantom: core::marker::PhantomData,
         }
-        Ok(())
     }
 }
 
 impl<'d> FlexspiNorStorageBus<'d, Blocking> {
     /// Create a new FlexSPI instance in blocking mode with single configuration
-    pub fn new_blocking_single_config<T: Instance>(
+    pub fn new_blocking_single_config<T: Instance, P: FlexSpiPort, C: FlexSpiCsPin<P>>(
         _inst: Peri<'d, T>,
-        data0: Peri<'d, impl FlexSpiPin>,
-        data1: Peri<'d, impl FlexSpiPin>,
-        clk: Peri<'d, impl FlexSpiPin>,
-        cs: Peri<'d, impl FlexSpiPin>,
+        data0: Peri<'d, impl FlexSpiPin<P>>,
+        data1: Peri<'d, impl FlexSpiPin<P>>,
+        clk: Peri<'d, impl FlexSpiPin<P>>,
+        cs: Peri<'d, C>,
         config: FlexspiConfigPortData,
     ) -> Self {
         // Configure the pins
@@ -1468,22 +3308,31 @@ impl<'d> FlexspiNorStorageBus<'d, Blocking> {
             _mode: core::marker::PhantomData,
             configport: FlexSpiConfigurationPort {
                 info: T::info(),
-                device_instance: config.dev_instance,
-                flash_port: config.port,
+                device_instance: C::DEVICE_INSTANCE,
+                flash_port: P::PORT,
+                bus_width: FlexSpiBusWidth::Single,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
             },
             rx_watermark: config.rx_watermark,
             tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: None,
+            tx_dma: None,
+            erase_suspended: false,
             phantom: core::marker::PhantomData,
         }
     }
 
     /// Create a new FlexSPI instance in blocking mode with Dual configuration
-    pub fn new_blocking_dual_config<T: Instance>(
+    pub fn new_blocking_dual_config<T: Instance, P: FlexSpiPort, C: FlexSpiCsPin<P>>(
         _inst: Peri<'d, T>,
-        data0: Peri<'d, impl FlexSpiPin>,
-        data1: Peri<'d, impl FlexSpiPin>,
-        clk: Peri<'d, impl FlexSpiPin>,
-        cs: Peri<'d, impl FlexSpiPin>,
+        data0: Peri<'d, impl FlexSpiPin<P>>,
+        data1: Peri<'d, impl FlexSpiPin<P>>,
+        clk: Peri<'d, impl FlexSpiPin<P>>,
+        cs: Peri<'d, C>,
         config: FlexspiConfigPortData,
     ) -> Self {
         // Configure the pins
@@ -1496,24 +3345,33 @@ impl<'d> FlexspiNorStorageBus<'d, Blocking> {
             _mode: core::marker::PhantomData,
             configport: FlexSpiConfigurationPort {
                 info: T::info(),
-                device_instance: config.dev_instance,
-                flash_port: config.port,
+                device_instance: C::DEVICE_INSTANCE,
+                flash_port: P::PORT,
+                bus_width: FlexSpiBusWidth::Dual,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
             },
             rx_watermark: config.rx_watermark,
             tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: None,
+            tx_dma: None,
+            erase_suspended: false,
             phantom: core::marker::PhantomData,
         }
     }
 
     /// Create a new FlexSPI instance in blocking mode with Quad configuration
-    pub fn new_blocking_quad_config<T: Instance>(
+    pub fn new_blocking_quad_config<T: Instance, P: FlexSpiPort, C: FlexSpiCsPin<P>>(
         _inst: Peri<'d, T>,
-        data0: Peri<'d, impl FlexSpiPin>,
-        data1: Peri<'d, impl FlexSpiPin>,
-        data2: Peri<'d, impl FlexSpiPin>,
-        data3: Peri<'d, impl FlexSpiPin>,
-        clk: Peri<'d, impl FlexSpiPin>,
-        cs: Peri<'d, impl FlexSpiPin>,
+        data0: Peri<'d, impl FlexSpiPin<P>>,
+        data1: Peri<'d, impl FlexSpiPin<P>>,
+        data2: Peri<'d, impl FlexSpiPin<P>>,
+        data3: Peri<'d, impl FlexSpiPin<P>>,
+        clk: Peri<'d, impl FlexSpiPin<P>>,
+        cs: Peri<'d, C>,
         config: FlexspiConfigPortData,
     ) -> Self {
         // Configure the pins
@@ -1528,28 +3386,39 @@ impl<'d> FlexspiNorStorageBus<'d, Blocking> {
             _mode: core::marker::PhantomData,
             configport: FlexSpiConfigurationPort {
                 info: T::info(),
-                device_instance: config.dev_instance,
-                flash_port: config.port,
+                device_instance: C::DEVICE_INSTANCE,
+                flash_port: P::PORT,
+                bus_width: FlexSpiBusWidth::Quad,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
             },
             rx_watermark: config.rx_watermark,
             tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: None,
+            tx_dma: None,
+            erase_suspended: false,
             phantom: core::marker::PhantomData,
         }
     }
 
     /// Create a new FlexSPI instance in blocking mode with octal configuration
-    pub fn new_blocking_octal_config<T: Instance>(
+    ///
+    /// `data4`-`data7` are the DATA4-7 pins of the same port.
+    pub fn new_blocking_octal_config<T: Instance, P: FlexSpiPort, C: FlexSpiCsPin<P>>(
         _inst: Peri<'d, T>,
-        data0: Peri<'d, impl FlexSpiPin>,
-        data1: Peri<'d, impl FlexSpiPin>,
-        data2: Peri<'d, impl FlexSpiPin>,
-        data3: Peri<'d, impl FlexSpiPin>,
-        data4: Peri<'d, impl FlexSpiPin>,
-        data5: Peri<'d, impl FlexSpiPin>,
-        data6: Peri<'d, impl FlexSpiPin>,
-        data7: Peri<'d, impl FlexSpiPin>,
-        clk: Peri<'d, impl FlexSpiPin>,
-        cs: Peri<'d, impl FlexSpiPin>,
+        data0: Peri<'d, impl FlexSpiPin<P>>,
+        data1: Peri<'d, impl FlexSpiPin<P>>,
+        data2: Peri<'d, impl FlexSpiPin<P>>,
+        data3: Peri<'d, impl FlexSpiPin<P>>,
+        data4: Peri<'d, impl FlexSpiPin<P>>,
+        data5: Peri<'d, impl FlexSpiPin<P>>,
+        data6: Peri<'d, impl FlexSpiPin<P>>,
+        data7: Peri<'d, impl FlexSpiPin<P>>,
+        clk: Peri<'d, impl FlexSpiPin<P>>,
+        cs: Peri<'d, C>,
         config: FlexspiConfigPortData,
     ) -> Self {
         // Configure the pins
@@ -1568,11 +3437,20 @@ impl<'d> FlexspiNorStorageBus<'d, Blocking> {
             _mode: core::marker::PhantomData,
             configport: FlexSpiConfigurationPort {
                 info: T::info(),
-                device_instance: config.dev_instance,
-                flash_port: config.port,
+                device_instance: C::DEVICE_INSTANCE,
+                flash_port: P::PORT,
+                bus_width: FlexSpiBusWidth::Octal,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
             },
             rx_watermark: config.rx_watermark,
             tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: None,
+            tx_dma: None,
+            erase_suspended: false,
             phantom: core::marker::PhantomData,
         }
     }
@@ -1586,20 +3464,32 @@ impl<'d> FlexspiNorStorageBus<'d, Blocking> {
                 info: T::info(),
                 device_instance: config.dev_instance,
                 flash_port: config.port,
+                bus_width: FlexSpiBusWidth::Octal,
+                rx_watermark: config.rx_watermark,
+                tx_watermark: config.tx_watermark,
+                read_only: config.read_only,
             },
             rx_watermark: config.rx_watermark,
             tx_watermark: config.tx_watermark,
+            read_only: config.read_only,
+            ahb_base: FLEXSPI_AHB_BASE,
+            rx_dma: None,
+            tx_dma: None,
+            erase_suspended: false,
             phantom: core::marker::PhantomData,
         }
     }
 }
 
 macro_rules! impl_pin {
-    ($peri:ident, $fn: ident) => {
-        impl FlexSpiPin for crate::peripherals::$peri {
+    ($peri:ident, $fn: ident, $port: ident) => {
+        impl FlexSpiPin<$port> for crate::peripherals::$peri {
             fn config_pin(&self) {
+                // Data lines are bidirectional and the controller samples DQS/SCLK loopback,
+                // so every FlexSPI pad needs its input buffer
                 self.set_function(crate::iopctl::Function::$fn)
                     .set_pull(crate::iopctl::Pull::None)
+                    .enable_input_buffer()
                     .set_slew_rate(crate::gpio::SlewRate::Slow)
                     .set_drive_strength(crate::gpio::DriveStrength::Normal)
                     .disable_analog_multiplex()
@@ -1610,31 +3500,86 @@ macro_rules! impl_pin {
     };
 }
 
-/// FlexSPI Data Pins
-pub trait FlexSpiPin: Pin + sealed::Sealed + PeripheralType {
-    /// Configure FlexSPI Data Pin
+macro_rules! impl_cs_pin {
+    ($peri:ident, $port: ident, $inst: ident) => {
+        impl FlexSpiCsPin<$port> for crate::peripherals::$peri {
+            const DEVICE_INSTANCE: FlexSpiFlashPortDeviceInstance = FlexSpiFlashPortDeviceInstance::$inst;
+        }
+    };
+}
+
+/// FlexSPI port as a type, so the constructors can check their pins at compile time
+pub trait FlexSpiPort: sealed::Sealed {
+    /// The port as a runtime value
+    const PORT: FlexSpiFlashPort;
+}
+
+/// FlexSPI Port A
+pub struct PortA;
+
+/// FlexSPI Port B
+pub struct PortB;
+
+impl FlexSpiPort for PortA {
+    const PORT: FlexSpiFlashPort = FlexSpiFlashPort::PortA;
+}
+
+impl FlexSpiPort for PortB {
+    const PORT: FlexSpiFlashPort = FlexSpiFlashPort::PortB;
+}
+
+/// FlexSPI pins of port `P`
+pub trait FlexSpiPin<P: FlexSpiPort>: Pin + sealed::Sealed + PeripheralType {
+    /// Configure the pin for FlexSPI on port `P`
     fn config_pin(&self);
 }
 
-impl_pin!(PIO1_11, F6); // PortB-DATA0
-impl_pin!(PIO1_12, F6); // PortB-DATA1
-impl_pin!(PIO1_13, F6); // PortB-DATA2
-impl_pin!(PIO1_14, F6); // PortB-DATA3
-impl_pin!(PIO2_17, F6); // PortB-DATA4
-impl_pin!(PIO2_18, F6); // PortB-DATA5
-impl_pin!(PIO2_22, F6); // PortB-DATA6
-impl_pin!(PIO2_23, F6); // PortB-DATA7
-impl_pin!(PIO2_19, F6); // PortB-CS0
-impl_pin!(PIO2_21, F6); // PortB-CS1
-impl_pin!(PIO1_29, F5); // PortB-SCLK
-
-impl_pin!(PIO1_19, F1); // PortA-CS0
-impl_pin!(PIO1_18, F1); // PortA-SCLK
-impl_pin!(PIO1_20, F1); // PortA-DATA0
-impl_pin!(PIO1_21, F1); // PortA-DATA1
-impl_pin!(PIO1_22, F1); // PortA-DATA2
-impl_pin!(PIO1_23, F1); // PortA-DATA3
-impl_pin!(PIO1_24, F1); // PortA-DATA4
-impl_pin!(PIO1_25, F1); // PortA-DATA5
-impl_pin!(PIO1_26, F1); // PortA-DATA6
-impl_pin!(PIO1_27, F1); // PortA-DATA7
+/// FlexSPI pins of Port A
+///
+/// Use as a bound to have the compiler reject pins of the other port.
+pub trait FlexSpiPortAPin: FlexSpiPin<PortA> {}
+
+impl<T: FlexSpiPin<PortA>> FlexSpiPortAPin for T {}
+
+/// FlexSPI pins of Port B
+///
+/// Use as a bound to have the compiler reject pins of the other port.
+pub trait FlexSpiPortBPin: FlexSpiPin<PortB> {}
+
+impl<T: FlexSpiPin<PortB>> FlexSpiPortBPin for T {}
+
+/// FlexSPI Chip Select Pins of port `P`
+pub trait FlexSpiCsPin<P: FlexSpiPort>: FlexSpiPin<P> {
+    /// Device instance selected by this chip select
+    const DEVICE_INSTANCE: FlexSpiFlashPortDeviceInstance;
+}
+
+impl_pin!(PIO1_11, F6, PortB); // PortB-DATA0
+impl_pin!(PIO1_12, F6, PortB); // PortB-DATA1
+impl_pin!(PIO1_13, F6, PortB); // PortB-DATA2
+impl_pin!(PIO1_14, F6, PortB); // PortB-DATA3
+impl_pin!(PIO2_17, F6, PortB); // PortB-DATA4
+impl_pin!(PIO2_18, F6, PortB); // PortB-DATA5
+impl_pin!(PIO2_22, F6, PortB); // PortB-DATA6
+impl_pin!(PIO2_23, F6, PortB); // PortB-DATA7
+impl_pin!(PIO2_19, F6, PortB); // PortB-CS0
+impl_pin!(PIO2_21, F6, PortB); // PortB-CS1
+impl_pin!(PIO1_29, F5, PortB); // PortB-SCLK
+
+impl_pin!(PIO1_19, F1, PortA); // PortA-CS0
+impl_pin!(PIO1_18, F1, PortA); // PortA-SCLK
+impl_pin!(PIO1_20, F1, PortA); // PortA-DATA0
+impl_pin!(PIO1_21, F1, PortA); // PortA-DATA1
+impl_pin!(PIO1_22, F1, PortA); // PortA-DATA2
+impl_pin!(PIO1_23, F1, PortA); // PortA-DATA3
+impl_pin!(PIO1_24, F1, PortA); // PortA-DATA4
+impl_pin!(PIO1_25, F1, PortA); // PortA-DATA5
+impl_pin!(PIO1_26, F1, PortA); // PortA-DATA6
+impl_pin!(PIO1_27, F1, PortA); // PortA-DATA7
+impl_pin!(PIO1_28, F1, PortA); // PortA-DQS
+impl_pin!(PIO2_19, F5, PortA); // PortA-CS1
+
+impl_cs_pin!(PIO2_19, PortB, DeviceInstance0);
+impl_cs_pin!(PIO2_21, PortB, DeviceInstance1);
+impl_cs_pin!(PIO1_19, PortA, DeviceInstance0);
+impl_cs_pin!(PIO2_19, PortA, DeviceInstance1);
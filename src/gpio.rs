@@ -229,11 +229,29 @@ impl<S: Sense> Flex<'_, S> {
             // There is not currently a "safe" method for setting a single-bit.
             unsafe { w.notp().bits(1 << self.pin.pin()) });
     }
+
+    /// Put the pin into its lowest-power state.
+    ///
+    /// The output driver is released (high-Z), the pull resistor and the input
+    /// buffer are disabled so the pad draws no leakage current. This is also
+    /// applied automatically when the pin is dropped.
+    pub fn set_as_disconnected(&mut self) {
+        self.pin.block().dirclr(self.pin.port()).write(|w|
+            // SAFETY: Writing a 0 to bits in this register has no effect,
+            // however PAC has it marked unsafe due to using the bits() method.
+            // There is not currently a "safe" method for setting a single-bit.
+            unsafe { w.dirclrp().bits(1 << self.pin.pin()) });
+
+        self.pin
+            .set_pull(Pull::None)
+            .disable_input_buffer()
+            .disable_analog_multiplex();
+    }
 }
 
 impl<S: Sense> Drop for Flex<'_, S> {
     fn drop(&mut self) {
-        self.pin.reset();
+        self.set_as_disconnected();
         // TODO: Disable clock for pin's port (assuming ref counted)?
     }
 }
@@ -417,6 +435,13 @@ impl<'d> Input<'d> {
     pub async fn wait_for_any_edge(&mut self) {
         self.pin.wait_for_any_edge().await;
     }
+
+    /// Release the pin, leaving its pad in the lowest-power state.
+    ///
+    /// Same as dropping the pin, see [`Flex::set_as_disconnected`].
+    pub fn into_lowpower(mut self) {
+        self.pin.set_as_disconnected();
+    }
 }
 
 #[must_use = "futures do nothing unless you `.await` or poll them"]
@@ -494,6 +519,9 @@ pub struct Output<'d> {
 
 impl<'d> Output<'d> {
     /// New output pin
+    ///
+    /// With [`DriveMode::OpenDrain`], `Level::High` releases the pin instead of driving it, e.g.
+    /// for an open-drain reset line: create it high and pulse it with `set_low`/`set_high`.
     pub fn new(
         pin: Peri<'d, impl GpioPin>,
         initial_output: Level,
@@ -539,6 +567,14 @@ impl<'d> Output<'d> {
     pub fn is_set_low(&self) -> bool {
         self.pin.is_set_low()
     }
+
+    /// Release the pin, leaving its pad in the lowest-power state.
+    ///
+    /// The pin stops driving whatever level was set. Same as dropping the pin,
+    /// see [`Flex::set_as_disconnected`].
+    pub fn into_lowpower(mut self) {
+        self.pin.set_as_disconnected();
+    }
 }
 
 trait SealedPin: IopctlPin {
@@ -826,7 +862,7 @@ impl<S: Sense> embedded_hal_02::digital::v2::OutputPin for Flex<'_, S> {
     }
 }
 
-impl embedded_hal_02::digital::v2::StatefulOutputPin for Flex<'_, SenseEnabled> {
+impl<S: Sense> embedded_hal_02::digital::v2::StatefulOutputPin for Flex<'_, S> {
     #[inline]
     fn is_set_high(&self) -> Result<bool, Self::Error> {
         Ok(self.is_set_high())
@@ -933,7 +969,7 @@ impl<S: Sense> embedded_hal_1::digital::OutputPin for Flex<'_, S> {
     }
 }
 
-impl embedded_hal_1::digital::StatefulOutputPin for Flex<'_, SenseEnabled> {
+impl<S: Sense> embedded_hal_1::digital::StatefulOutputPin for Flex<'_, S> {
     #[inline]
     fn is_set_high(&mut self) -> Result<bool, Self::Error> {
         Ok((*self).is_set_high())
@@ -943,6 +979,12 @@ impl embedded_hal_1::digital::StatefulOutputPin for Flex<'_, SenseEnabled> {
     fn is_set_low(&mut self) -> Result<bool, Self::Error> {
         Ok((*self).is_set_low())
     }
+
+    #[inline]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        (*self).toggle();
+        Ok(())
+    }
 }
 
 impl<'d> embedded_hal_async::digital::Wait for Flex<'d, SenseEnabled> {
@@ -1053,4 +1095,10 @@ impl embedded_hal_1::digital::StatefulOutputPin for Output<'_> {
     fn is_set_low(&mut self) -> Result<bool, Self::Error> {
         Ok((*self).is_set_low())
     }
+
+    #[inline]
+    fn toggle(&mut self) -> Result<(), Self::Error> {
+        (*self).toggle();
+        Ok(())
+    }
 }
@@ -9,9 +9,9 @@ use embassy_hal_internal::drop::OnDrop;
 use itertools::Itertools;
 
 use super::{
-    force_clear_remediation, wait_remediation_complete, Async, Blocking, Error, Info, Instance, InterruptHandler,
-    MasterDma, Mode, Result, SclPin, SdaPin, TransferError, I2C_REMEDIATION, I2C_WAKERS, REMEDIATON_MASTER_STOP,
-    TEN_BIT_PREFIX,
+    debug_check_bus_pin, force_clear_remediation, wait_remediation_complete, Async, Blocking, Error, Info, Instance,
+    InterruptHandler, MasterDma, Mode, Result, SclPin, SdaPin, TransferError, I2C_REMEDIATION, I2C_WAKERS,
+    REMEDIATON_MASTER_STOP, TEN_BIT_PREFIX,
 };
 use crate::flexcomm::FlexcommRef;
 use crate::interrupt::typelevel::Interrupt;
@@ -34,6 +34,8 @@ pub enum Speed {
     High,
 }
 
+const SFRO_CLOCK_SPEED_HZ: u32 = 16_000_000;
+
 /// Divide integers rounding to the nearest whole number rather than always down
 fn rounded_divide(numerator: u32, denominator: u32) -> u32 {
     (numerator + denominator / 2) / denominator
@@ -91,6 +93,10 @@ impl IntoClocksEnum<Mstsclhigh> for u8 {
     }
 }
 
+// The timeout counter runs on the I2C function clock in units of 16 clocks
+const TIMEOUT_CLOCKS_PER_TICK: u64 = 16;
+const TIMEOUT_MAX_TICKS: u64 = 0x1000;
+
 struct SpeedRegisterSettings {
     scl_high_clocks: Mstsclhigh,
     scl_low_clocks: Mstscllow,
@@ -101,8 +107,6 @@ struct SpeedRegisterSettings {
 
 impl SpeedRegisterSettings {
     fn new(duty_cycle: DutyCycle, speed: Speed) -> Result<Self> {
-        const SFRO_CLOCK_SPEED_HZ: u32 = 16_000_000;
-
         let target_freq_hz: u32 = match speed {
             Speed::Standard => 100_000,   // 100 KHz
             Speed::Fast => 400_000,       // 400 KHz
@@ -147,6 +151,25 @@ impl SpeedRegisterSettings {
     }
 }
 
+/// Report and clear a bus or SCL timeout flagged by the FLEXCOMM timeout hardware
+fn check_for_timeout(regs: &crate::pac::i2c0::RegisterBlock) -> Result<()> {
+    let stat = regs.stat().read();
+
+    if stat.eventtimeout().bit_is_set() || stat.scltimeout().bit_is_set() {
+        regs.stat()
+            .write(|w| w.eventtimeout().clear_bit_by_one().scltimeout().clear_bit_by_one());
+
+        // A stuck SCL is a hard bus fault, an idle bus event timeout may just be a slow device
+        if stat.scltimeout().bit_is_set() {
+            Err(TransferError::SclTimeout.into())
+        } else {
+            Err(TransferError::Timeout.into())
+        }
+    } else {
+        Ok(())
+    }
+}
+
 /// use `FCn` as I2C Master controller
 pub struct I2cMaster<'a, M: Mode> {
     info: Info,
@@ -209,6 +232,15 @@ pub struct Config {
 
     /// The target duty cycle (percentage of time to hold the SCL line high per bit).
     pub duty_cycle: DutyCycle,
+
+    /// Bus timeout in microseconds, `None` to wait forever.
+    ///
+    /// Uses the FLEXCOMM timeout hardware. Transfers fail with [`TransferError::SclTimeout`]
+    /// when SCL is held low for longer than this, e.g. by a stuck device, and with
+    /// [`TransferError::Timeout`] when the bus shows no activity for longer than this. The
+    /// value is rounded down to a multiple of 16 I2C function clocks and saturates at the
+    /// longest timeout the hardware supports.
+    pub timeout_us: Option<u32>,
 }
 
 impl Default for Config {
@@ -216,6 +248,7 @@ impl Default for Config {
         Self {
             speed: Speed::Standard,
             duty_cycle: Default::default(),
+            timeout_us: None,
         }
     }
 }
@@ -236,6 +269,8 @@ impl<'a, M: Mode> I2cMaster<'a, M> {
 
         sda.as_sda();
         scl.as_scl();
+        debug_check_bus_pin(&*sda);
+        debug_check_bus_pin(&*scl);
 
         let info = T::info();
         let regs = info.regs;
@@ -256,7 +291,18 @@ impl<'a, M: Mode> I2cMaster<'a, M> {
 
         regs.intenset().reset();
 
-        regs.cfg().write(|w| w.msten().set_bit());
+        if let Some(timeout_us) = config.timeout_us {
+            let function_clock_hz = SFRO_CLOCK_SPEED_HZ / (u32::from(speed_settings.clock_div_multiplier) + 1);
+            let ticks = (u64::from(timeout_us) * u64::from(function_clock_hz) / 1_000_000 / TIMEOUT_CLOCKS_PER_TICK)
+                .clamp(1, TIMEOUT_MAX_TICKS);
+
+            regs.timeout().write(|w|
+                // SAFETY: only unsafe due to .bits usage, ticks is clamped to the 12 bit field
+                unsafe { w.tomin().bits(0xF).to().bits((ticks - 1) as u16) });
+            regs.cfg().write(|w| w.msten().set_bit().timeouten().set_bit());
+        } else {
+            regs.cfg().write(|w| w.msten().set_bit());
+        }
 
         Ok(Self {
             info,
@@ -269,6 +315,8 @@ impl<'a, M: Mode> I2cMaster<'a, M> {
     fn check_for_bus_errors(&self) -> Result<()> {
         let i2cregs = self.info.regs;
 
+        check_for_timeout(i2cregs)?;
+
         if i2cregs.stat().read().mstarbloss().is_arbitration_loss() {
             Err(TransferError::ArbitrationLoss.into())
         } else if i2cregs.stat().read().mstststperr().is_error() {
@@ -398,14 +446,19 @@ impl<'a> I2cMaster<'a, Blocking> {
     }
 
     fn read_no_stop(&mut self, address: u16, read: &mut [u8]) -> Result<()> {
-        let i2cregs = self.info.regs;
-
         // read of 0 size is not allowed according to i2c spec
         if read.is_empty() {
             return Err(TransferError::OtherBusError.into());
         }
 
         self.start(address, true)?;
+        self.read_bytes(read, false)
+    }
+
+    // Clock in `read` after the read address phase. The last byte is only ACKed when
+    // `ack_last` is set, i.e. when the following operation continues the same read.
+    fn read_bytes(&mut self, read: &mut [u8], ack_last: bool) -> Result<()> {
+        let i2cregs = self.info.regs;
 
         let read_len = read.len();
 
@@ -422,7 +475,7 @@ impl<'a> I2cMaster<'a, Blocking> {
             *r = i2cregs.mstdat().read().data().bits();
 
             // continue after ACK until last byte
-            if i < read_len - 1 {
+            if i < read_len - 1 || ack_last {
                 i2cregs.mstctl().write(|w| w.mstcontinue().set_bit());
             }
         }
@@ -431,11 +484,14 @@ impl<'a> I2cMaster<'a, Blocking> {
     }
 
     fn write_no_stop(&mut self, address: u16, write: &[u8]) -> Result<()> {
+        self.start(address, false)?;
+        self.write_bytes(write)
+    }
+
+    fn write_bytes(&mut self, write: &[u8]) -> Result<()> {
         // Procedure from 24.3.1.1 pg 545
         let i2cregs = self.info.regs;
 
-        self.start(address, false)?;
-
         for byte in write {
             i2cregs.mstdat().write(|w|
                 // SAFETY: unsafe only due to .bits usage
@@ -467,7 +523,9 @@ impl<'a> I2cMaster<'a, Blocking> {
     }
 
     fn poll_ready(&mut self) -> Result<()> {
-        while self.info.regs.stat().read().mstpending().is_in_progress() {}
+        while self.info.regs.stat().read().mstpending().is_in_progress() {
+            check_for_timeout(self.info.regs)?;
+        }
 
         Ok(())
     }
@@ -660,24 +718,47 @@ impl<'a> I2cMaster<'a, Async> {
         Ok(guard)
     }
 
-    async fn read_no_stop(
+    pub(super) async fn read_no_stop(
         &mut self,
         address: u16,
         read: &mut [u8],
         guard: Option<StartStopGuard>,
     ) -> Result<StartStopGuard> {
-        let i2cregs = self.info.regs;
+        // read of 0 size is not allowed according to i2c spec
+        if read.is_empty() {
+            return Err(TransferError::OtherBusError.into());
+        }
+
+        let guard = self.start(address, true, guard).await?;
+        self.read_bytes(read).await?;
+
+        Ok(guard)
+    }
 
+    /// ACK the last byte of the preceding read and clock in `read` as part of the same
+    /// transfer, without a repeated start. Lets a caller size the rest of a read from
+    /// data it already received, e.g. an SMBus block count.
+    pub(super) async fn read_continue(&mut self, read: &mut [u8]) -> Result<()> {
         // read of 0 size is not allowed according to i2c spec
-        //
+        if read.is_empty() {
+            return Err(TransferError::OtherBusError.into());
+        }
+
+        self.info.regs.mstctl().write(|w| w.mstcontinue().set_bit());
+        self.read_bytes(read).await
+    }
+
+    // Clock in `read` once the controller is receiving. The last byte is left
+    // unacknowledged, for a stop, a repeated start or `read_continue` to follow.
+    async fn read_bytes(&mut self, read: &mut [u8]) -> Result<()> {
+        let i2cregs = self.info.regs;
+
         // These are used in the DMA branch, but also checks implicitly
         // if the slice is empty, so we do it here anyway
         let Some((last_byte, dma_read)) = read.split_last_mut() else {
             return Err(TransferError::OtherBusError.into());
         };
 
-        let guard = self.start(address, true, guard).await?;
-
         if self.dma_ch.is_some() {
             if !dma_read.is_empty() {
                 let transfer = dma::transfer::Transfer::new_read(
@@ -713,8 +794,16 @@ impl<'a> I2cMaster<'a, Async> {
                                 .set_bit()
                                 .mstststperren()
                                 .set_bit()
+                                .eventtimeouten()
+                                .set_bit()
+                                .scltimeouten()
+                                .set_bit()
                         });
 
+                        if let Err(e) = check_for_timeout(i2cregs) {
+                            return Poll::Ready(Err(e));
+                        }
+
                         let stat = i2cregs.stat().read();
 
                         if stat.mstarbloss().is_arbitration_loss() {
@@ -809,22 +898,29 @@ impl<'a> I2cMaster<'a, Async> {
                 }
             }
         }
-        Ok(guard)
+        Ok(())
     }
 
-    async fn write_no_stop(
+    pub(super) async fn write_no_stop(
         &mut self,
         address: u16,
         write: &[u8],
         guard: Option<StartStopGuard>,
     ) -> Result<StartStopGuard> {
         // Procedure from 24.3.1.1 pg 545
-        let i2cregs = self.info.regs;
-
         let guard = self.start(address, false, guard).await?;
+        self.write_bytes(write).await?;
+
+        Ok(guard)
+    }
+
+    // Clock out `write` once the controller is transmitting, without issuing a
+    // start. Lets `transaction` merge adjacent writes into a single transfer.
+    async fn write_bytes(&mut self, write: &[u8]) -> Result<()> {
+        let i2cregs = self.info.regs;
 
         if write.is_empty() {
-            return Ok(guard);
+            return Ok(());
         }
 
         if self.dma_ch.is_some() {
@@ -861,8 +957,16 @@ impl<'a> I2cMaster<'a, Async> {
                             .set_bit()
                             .mstststperren()
                             .set_bit()
+                            .eventtimeouten()
+                            .set_bit()
+                            .scltimeouten()
+                            .set_bit()
                     });
 
+                    if let Err(e) = check_for_timeout(i2cregs) {
+                        return Poll::Ready(Err(e));
+                    }
+
                     let stat = i2cregs.stat().read();
 
                     if stat.mstarbloss().is_arbitration_loss() {
@@ -909,7 +1013,7 @@ impl<'a> I2cMaster<'a, Async> {
                 },
             )
             .await?;
-            Ok(guard)
+            Ok(())
         } else {
             for byte in write.iter() {
                 i2cregs.mstdat().write(|w|
@@ -947,11 +1051,11 @@ impl<'a> I2cMaster<'a, Async> {
 
                 self.check_for_bus_errors()?;
             }
-            Ok(guard)
+            Ok(())
         }
     }
 
-    async fn stop(&mut self) -> Result<()> {
+    pub(super) async fn stop(&mut self) -> Result<()> {
         // Procedure from 24.3.1.1 pg 545
         let i2cregs = self.info.regs;
 
@@ -991,19 +1095,30 @@ impl<'a> I2cMaster<'a, Async> {
 
     /// Calls `f` to check if we are ready or not.
     /// If not, `g` is called once the waker is set (to eg enable the required interrupts).
-    async fn wait_on<F, U, G>(&mut self, mut f: F, mut g: G) -> U
+    /// A bus timeout flagged by the hardware ends the wait with [`TransferError::Timeout`].
+    async fn wait_on<F, G>(&mut self, mut f: F, mut g: G) -> Result<()>
     where
-        F: FnMut(&mut Self) -> Poll<U>,
+        F: FnMut(&mut Self) -> Poll<Result<()>>,
         G: FnMut(&mut Self),
     {
         poll_fn(|cx| {
             // Register waker before checking condition, to ensure that wakes/interrupts
             // aren't lost between f() and g()
             I2C_WAKERS[self.info.index].register(cx.waker());
+
+            // A stretched or stuck bus never reaches the state f() waits for
+            if let Err(e) = check_for_timeout(self.info.regs) {
+                return Poll::Ready(Err(e));
+            }
+
             let r = f(self);
 
             if r.is_pending() {
                 g(self);
+                self.info
+                    .regs
+                    .intenset()
+                    .write(|w| w.eventtimeouten().set_bit().scltimeouten().set_bit());
             }
 
             r
@@ -1053,6 +1168,29 @@ impl<'a> I2cMaster<'a, Async> {
     }
 }
 
+#[cfg(feature = "time")]
+impl I2cMaster<'_, Async> {
+    /// `write_read` bounded by an overall `timeout`, ending with [`TransferError::Timeout`]
+    ///
+    /// The bus timeout of [`Config::timeout_us`] only fires while the bus is stalled, this one
+    /// bounds the whole transfer, e.g. for polling loops on a device that may be missing. A
+    /// transfer cut short is stopped on the bus before the next one starts.
+    pub async fn write_read_with_timeout(
+        &mut self,
+        address: impl Into<u16>,
+        write: &[u8],
+        read: &mut [u8],
+        timeout: embassy_time::Duration,
+    ) -> Result<()> {
+        embassy_time::with_timeout(
+            timeout,
+            embedded_hal_async::i2c::I2c::write_read(self, address.into(), write, read),
+        )
+        .await
+        .unwrap_or(Err(TransferError::Timeout.into()))
+    }
+}
+
 /// Error Types for I2C communication
 impl embedded_hal_1::i2c::Error for Error {
     fn kind(&self) -> embedded_hal_1::i2c::ErrorKind {
@@ -1060,6 +1198,7 @@ impl embedded_hal_1::i2c::Error for Error {
             Self::UnsupportedConfiguration => embedded_hal_1::i2c::ErrorKind::Other,
             Self::Transfer(e) => match e {
                 TransferError::Timeout => embedded_hal_1::i2c::ErrorKind::Other,
+                TransferError::SclTimeout => embedded_hal_1::i2c::ErrorKind::Bus,
                 TransferError::ReadFail | TransferError::WriteFail => {
                     embedded_hal_1::i2c::ErrorKind::NoAcknowledge(embedded_hal_1::i2c::NoAcknowledgeSource::Data)
                 }
@@ -1069,6 +1208,7 @@ impl embedded_hal_1::i2c::Error for Error {
                 TransferError::ArbitrationLoss => embedded_hal_1::i2c::ErrorKind::ArbitrationLoss,
                 TransferError::StartStopError => embedded_hal_1::i2c::ErrorKind::Bus,
                 TransferError::OtherBusError => embedded_hal_1::i2c::ErrorKind::Bus,
+                TransferError::PecError => embedded_hal_1::i2c::ErrorKind::Other,
             },
         }
     }
@@ -1101,13 +1241,31 @@ impl<A: embedded_hal_1::i2c::AddressMode + Into<u16>> embedded_hal_1::i2c::I2c<A
         let needs_stop = !operations.is_empty();
         let address = address.into();
 
-        for op in operations {
+        // Adjacent operations of the same type are one transfer: only a change of direction
+        // gets a (repeated) start, and reads followed by another read keep ACKing
+        let mut last_is_read = None;
+        let mut ops = operations.iter_mut().peekable();
+        while let Some(op) = ops.next() {
+            let next_is_read = matches!(ops.peek(), Some(embedded_hal_1::i2c::Operation::Read(_)));
+
             match op {
                 embedded_hal_1::i2c::Operation::Read(read) => {
-                    self.read_no_stop(address, read)?;
+                    // read of 0 size is not allowed according to i2c spec
+                    if read.is_empty() {
+                        return Err(TransferError::OtherBusError.into());
+                    }
+                    if last_is_read != Some(true) {
+                        self.start(address, true)?;
+                    }
+                    self.read_bytes(read, next_is_read)?;
+                    last_is_read = Some(true);
                 }
                 embedded_hal_1::i2c::Operation::Write(write) => {
-                    self.write_no_stop(address, write)?;
+                    if last_is_read != Some(false) {
+                        self.start(address, false)?;
+                    }
+                    self.write_bytes(write)?;
+                    last_is_read = Some(false);
                 }
             }
         }
@@ -1148,13 +1306,26 @@ impl<A: embedded_hal_1::i2c::AddressMode + Into<u16>> embedded_hal_async::i2c::I
         let address = address.into();
         let mut guard = None;
 
+        // Adjacent operations of the same type are one transfer: only a change of direction
+        // gets a (repeated) start, and reads followed by another read keep ACKing
+        let mut last_is_read = None;
         for op in operations {
             match op {
                 embedded_hal_1::i2c::Operation::Read(read) => {
-                    guard = Some(self.read_no_stop(address, read, guard).await?);
+                    if last_is_read == Some(true) {
+                        self.read_continue(read).await?;
+                    } else {
+                        guard = Some(self.read_no_stop(address, read, guard).await?);
+                    }
+                    last_is_read = Some(true);
                 }
                 embedded_hal_1::i2c::Operation::Write(write) => {
-                    guard = Some(self.write_no_stop(address, write, guard).await?);
+                    if last_is_read == Some(false) {
+                        self.write_bytes(write).await?;
+                    } else {
+                        guard = Some(self.write_no_stop(address, write, guard).await?);
+                    }
+                    last_is_read = Some(false);
                 }
             }
         }
@@ -1179,12 +1350,12 @@ impl<A: embedded_hal_1::i2c::AddressMode + Into<u16>> embedded_hal_async::i2c::I
 /// or leaving the bus in the un-stopped state, we ask the interrupt handler to do
 /// it for us.
 #[must_use]
-struct StartStopGuard {
+pub(super) struct StartStopGuard {
     info: Info,
 }
 
 impl StartStopGuard {
-    fn defuse(self) {
+    pub(super) fn defuse(self) {
         core::mem::forget(self);
     }
 }
@@ -18,6 +18,9 @@ pub mod master;
 /// I2C Slave Driver
 pub mod slave;
 
+/// SMBus helpers
+pub mod smbus;
+
 /// shorthand for -> `Result<T>`
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -27,6 +30,11 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum TransferError {
     /// Timeout error
     Timeout,
+    /// SCL held low past the configured timeout, e.g. a device stuck driving the bus
+    ///
+    /// Only SCL is monitored: a device holding SDA low still surfaces as
+    /// [`TransferError::StartStopError`] or [`TransferError::OtherBusError`].
+    SclTimeout,
     /// Reading from i2c failed
     ReadFail,
     /// Writing to i2c failed
@@ -39,6 +47,8 @@ pub enum TransferError {
     StartStopError,
     /// state mismatch or other internal register unexpected state
     OtherBusError,
+    /// SMBus Packet Error Code mismatch
+    PecError,
 }
 
 /// Error information type
@@ -189,6 +199,11 @@ impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandl
             i2c.intenclr().write(|w| w.mstststperrclr().set_bit());
         }
 
+        if i2c.intstat().read().eventtimeout().bit_is_set() || i2c.intstat().read().scltimeout().bit_is_set() {
+            i2c.intenclr()
+                .write(|w| w.eventtimeoutclr().set_bit().scltimeoutclr().set_bit());
+        }
+
         if i2c.intstat().read().slvpending().bit_is_set() {
             // Retrieve and mask off the remediation flags
             let rem = I2C_REMEDIATION[T::index()].fetch_and(!REMEDIATON_SLAVE_NAK, Ordering::AcqRel);
@@ -208,17 +223,35 @@ impl<T: Instance> interrupt::typelevel::Handler<T::Interrupt> for InterruptHandl
 }
 
 /// io configuration trait for easier configuration
+///
+/// Only implemented for pins that can be muxed to the SCL function of the given Flexcomm
+/// `Instance`, so passing a pin that cannot act as SCL for that instance fails to compile.
 pub trait SclPin<Instance>: Pin + sealed::Sealed + PeripheralType {
     /// convert the pin to appropriate function for SCL usage
     fn as_scl(&self);
 }
 
 /// io configuration trait for easier configuration
+///
+/// Only implemented for pins that can be muxed to the SDA function of the given Flexcomm
+/// `Instance`, so passing a pin that cannot act as SDA for that instance fails to compile.
 pub trait SdaPin<Instance>: Pin + sealed::Sealed + PeripheralType {
     /// convert the pin to appropriate function for SDA usage
     fn as_sda(&self);
 }
 
+// SDA and SCL must only ever pull the bus low, and the controller reads the bus level back
+// through the input buffer to see clock stretching and arbitration loss
+fn debug_check_bus_pin(pin: &impl Pin) {
+    if cfg!(debug_assertions) {
+        let config = pin.read_config();
+        assert!(
+            config.drive_mode == crate::gpio::DriveMode::OpenDrain && config.input_buffer,
+            "I2C pin is not open-drain with its input buffer enabled"
+        );
+    }
+}
+
 /// Driver mode.
 #[allow(private_bounds)]
 pub trait Mode: Sealed {}
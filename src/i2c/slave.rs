@@ -9,8 +9,8 @@ use embassy_hal_internal::drop::OnDrop;
 use embassy_hal_internal::Peri;
 
 use super::{
-    Async, Blocking, Info, Instance, InterruptHandler, Mode, Result, SclPin, SdaPin, SlaveDma, TransferError,
-    I2C_REMEDIATION, I2C_WAKERS, REMEDIATON_SLAVE_NAK, TEN_BIT_PREFIX,
+    debug_check_bus_pin, Async, Blocking, Info, Instance, InterruptHandler, Mode, Result, SclPin, SdaPin, SlaveDma,
+    TransferError, I2C_REMEDIATION, I2C_WAKERS, REMEDIATON_SLAVE_NAK, TEN_BIT_PREFIX,
 };
 use crate::flexcomm::FlexcommRef;
 use crate::interrupt::typelevel::Interrupt;
@@ -19,6 +19,7 @@ use crate::{dma, interrupt};
 
 /// Address errors
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum AddressError {
     /// Invalid address conversion
     InvalidConversion,
@@ -26,6 +27,7 @@ pub enum AddressError {
 
 /// I2C address type
 #[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Address {
     /// 7-bit address
     SevenBit(u8),
@@ -109,6 +111,8 @@ impl TenBitAddressInfo {
 }
 
 /// Command from master
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Command {
     /// I2C probe with no data
     Probe,
@@ -121,6 +125,8 @@ pub enum Command {
 }
 
 /// Result of response functions
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum Response {
     /// I2C transaction complete with this amount of bytes
     Complete(usize),
@@ -155,6 +161,8 @@ impl<'a, M: Mode> I2cSlave<'a, M> {
 
         sda.as_sda();
         scl.as_scl();
+        debug_check_bus_pin(&*sda);
+        debug_check_bus_pin(&*scl);
 
         // this check should be redundant with T::set_mode()? above
         let info = T::info();
@@ -0,0 +1,218 @@
+//! SMBus protocol helpers layered on top of the I2C master
+
+use embedded_hal_async::i2c::{I2c, Operation};
+
+use super::master::I2cMaster;
+use super::{Async, Error, Result, TransferError};
+
+/// SMBus Device Default Address used for Address Resolution Protocol
+pub const ARP_ADDRESS: u8 = 0x61;
+
+/// ARP command: Prepare to ARP
+const ARP_CMD_PREPARE: u8 = 0x01;
+/// ARP command: Reset Device (general)
+const ARP_CMD_RESET_DEVICE: u8 = 0x02;
+/// ARP command: Get UDID (general)
+const ARP_CMD_GET_UDID: u8 = 0x03;
+/// ARP command: Assign Address
+const ARP_CMD_ASSIGN_ADDRESS: u8 = 0x04;
+
+/// Byte count of the Get UDID / Assign Address block payload (UDID + address)
+const ARP_BLOCK_LEN: u8 = 17;
+
+/// Length of the SMBus Unique Device Identifier
+pub const UDID_LEN: usize = 16;
+
+/// Largest payload of an SMBus 3.0 block transfer, bounded by the one byte count
+pub const MAX_BLOCK_LEN: usize = 255;
+
+/// Compute the SMBus Packet Error Code (CRC-8, polynomial x^8 + x^2 + x + 1) over `data`,
+/// continuing from a previous `crc` value (use 0 to start).
+pub fn pec(crc: u8, data: &[u8]) -> u8 {
+    data.iter().fold(crc, |mut crc, byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+        }
+        crc
+    })
+}
+
+/// SMBus Unique Device Identifier returned by ARP Get UDID
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Udid(pub [u8; UDID_LEN]);
+
+/// Device reported by an ARP Get UDID response
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ArpDevice {
+    /// Unique device identifier
+    pub udid: Udid,
+    /// Current slave address of the device (7-bit), 0x7F if none is assigned
+    pub address: u8,
+}
+
+impl I2cMaster<'_, Async> {
+    /// Send an SMBus command with PEC and no data (ARP "send byte" style command)
+    async fn arp_send_command(&mut self, cmd: u8) -> Result<()> {
+        let crc = pec(0, &[ARP_ADDRESS << 1, cmd]);
+        self.write(ARP_ADDRESS, &[cmd, crc]).await
+    }
+
+    /// Issue ARP "Prepare to ARP" to every ARP capable device on the bus
+    pub async fn arp_prepare(&mut self) -> Result<()> {
+        self.arp_send_command(ARP_CMD_PREPARE).await
+    }
+
+    /// Issue ARP general "Reset Device", clearing the address resolved flag of all devices
+    pub async fn arp_reset_devices(&mut self) -> Result<()> {
+        self.arp_send_command(ARP_CMD_RESET_DEVICE).await
+    }
+
+    /// Issue ARP general "Get UDID".
+    ///
+    /// Returns `Ok(None)` when no device without an assigned address is left on the bus.
+    pub async fn arp_get_udid(&mut self) -> Result<Option<ArpDevice>> {
+        // byte count, UDID, device address and PEC
+        let mut response = [0_u8; 1 + ARP_BLOCK_LEN as usize + 1];
+
+        match self
+            .transaction(
+                ARP_ADDRESS,
+                &mut [Operation::Write(&[ARP_CMD_GET_UDID]), Operation::Read(&mut response)],
+            )
+            .await
+        {
+            Err(Error::Transfer(TransferError::AddressNack)) => return Ok(None),
+            result => result?,
+        }
+
+        if response[0] != ARP_BLOCK_LEN {
+            return Err(TransferError::ReadFail.into());
+        }
+
+        let (payload, received_pec) = response.split_at(response.len() - 1);
+        let crc = pec(
+            pec(0, &[ARP_ADDRESS << 1, ARP_CMD_GET_UDID, (ARP_ADDRESS << 1) | 1]),
+            payload,
+        );
+        if crc != received_pec[0] {
+            return Err(TransferError::PecError.into());
+        }
+
+        let mut udid = [0_u8; UDID_LEN];
+        udid.copy_from_slice(&payload[1..=UDID_LEN]);
+
+        Ok(Some(ArpDevice {
+            udid: Udid(udid),
+            address: payload[UDID_LEN + 1] >> 1,
+        }))
+    }
+
+    /// Issue ARP "Assign Address", assigning the 7-bit `address` to the device identified by `udid`
+    pub async fn arp_assign_address(&mut self, udid: &Udid, address: u8) -> Result<()> {
+        if address > 0x7F {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        // command, byte count, UDID, address and PEC
+        let mut request = [0_u8; 2 + ARP_BLOCK_LEN as usize + 1];
+        request[0] = ARP_CMD_ASSIGN_ADDRESS;
+        request[1] = ARP_BLOCK_LEN;
+        request[2..2 + UDID_LEN].copy_from_slice(&udid.0);
+        request[2 + UDID_LEN] = address << 1;
+
+        let (payload, crc) = request.split_at_mut(2 + ARP_BLOCK_LEN as usize);
+        crc[0] = pec(pec(0, &[ARP_ADDRESS << 1]), payload);
+
+        self.write(ARP_ADDRESS, &request).await
+    }
+
+    /// Run a full ARP enumeration, assigning addresses from `addresses` in order to every
+    /// device that answers Get UDID.
+    ///
+    /// Returns the number of devices that were assigned an address.
+    pub async fn arp_enumerate(&mut self, addresses: &[u8]) -> Result<usize> {
+        self.arp_prepare().await?;
+
+        let mut assigned = 0;
+        for &address in addresses {
+            let Some(device) = self.arp_get_udid().await? else {
+                break;
+            };
+
+            self.arp_assign_address(&device.udid, address).await?;
+            assigned += 1;
+        }
+
+        Ok(assigned)
+    }
+
+    /// SMBus Block Write: send `cmd`, a byte count and `data` to the 7-bit `address`,
+    /// followed by a PEC byte when `use_pec` is set.
+    pub async fn smbus_write_block(&mut self, address: u8, cmd: u8, data: &[u8], use_pec: bool) -> Result<()> {
+        if data.len() > MAX_BLOCK_LEN {
+            return Err(Error::UnsupportedConfiguration);
+        }
+
+        // command, byte count, data and PEC
+        let mut request = [0_u8; 2 + MAX_BLOCK_LEN + 1];
+        request[0] = cmd;
+        request[1] = data.len() as u8;
+        request[2..2 + data.len()].copy_from_slice(data);
+
+        let mut len = 2 + data.len();
+        if use_pec {
+            request[len] = pec(pec(0, &[address << 1]), &request[..len]);
+            len += 1;
+        }
+
+        self.write(address, &request[..len]).await
+    }
+
+    /// SMBus Block Read: send `cmd` to the 7-bit `address` and read back a counted block into
+    /// `data`, checking the trailing PEC byte when `use_pec` is set.
+    ///
+    /// The byte count is read first and exactly that many data bytes (plus the PEC) are clocked
+    /// in after it, in the same read transfer. Returns the number of valid bytes in `data`, or
+    /// [`TransferError::ReadFail`] if the device announces a block larger than `data`.
+    pub async fn smbus_read_block(&mut self, address: u8, cmd: u8, data: &mut [u8], use_pec: bool) -> Result<usize> {
+        // No block is longer than MAX_BLOCK_LEN, any extra room in `data` is left untouched
+        let capacity = data.len().min(MAX_BLOCK_LEN);
+
+        // byte count, data and PEC
+        let mut response = [0_u8; 1 + MAX_BLOCK_LEN + 1];
+
+        let guard = self.write_no_stop(address.into(), &[cmd], None).await?;
+        let guard = self
+            .read_no_stop(address.into(), &mut response[..1], Some(guard))
+            .await?;
+
+        let count = response[0] as usize;
+        if count > capacity {
+            // NACK the byte count so the device releases the bus
+            self.stop().await?;
+            guard.defuse();
+            return Err(TransferError::ReadFail.into());
+        }
+
+        let len = 1 + count + use_pec as usize;
+        if len > 1 {
+            self.read_continue(&mut response[1..len]).await?;
+        }
+        self.stop().await?;
+        guard.defuse();
+
+        if use_pec {
+            let crc = pec(pec(0, &[address << 1, cmd, (address << 1) | 1]), &response[..1 + count]);
+            if crc != response[1 + count] {
+                return Err(TransferError::PecError.into());
+            }
+        }
+
+        data[..count].copy_from_slice(&response[1..1 + count]);
+
+        Ok(count)
+    }
+}
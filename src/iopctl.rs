@@ -77,6 +77,10 @@ pub enum DriveMode {
     /// Push-Pull
     PushPull,
     /// Pseudo Open-Drain
+    ///
+    /// Sets IOPCTL ODENA: the pin only drives low and is released when the output is high,
+    /// so it can share a wire-OR net such as an open-drain reset line with other drivers.
+    /// The high level comes from the external pull-up on the net.
     OpenDrain,
 }
 
@@ -91,6 +95,28 @@ pub enum Inverter {
     Enabled,
 }
 
+/// Configuration of a pin as currently programmed in its IOPCTL register.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PinConfig {
+    /// Selected function, `None` for a reserved function number
+    pub function: Option<Function>,
+    /// Pull-up/down resistor
+    pub pull: Pull,
+    /// Input buffer enabled
+    pub input_buffer: bool,
+    /// Slew rate
+    pub slew_rate: SlewRate,
+    /// Output drive strength
+    pub drive_strength: DriveStrength,
+    /// Analog multiplexer enabled
+    pub analog_multiplex: bool,
+    /// Output drive mode
+    pub drive_mode: DriveMode,
+    /// Input inverter
+    pub inverter: Inverter,
+}
+
 trait SealedPin {}
 trait ToAnyPin: SealedPin {
     #[inline]
@@ -182,6 +208,11 @@ pub trait IopctlPin: SealedPin {
 
     /// Returns a pin to its reset state.
     fn reset(&self) -> &Self;
+
+    /// Reads back the live configuration of a pin.
+    ///
+    /// Useful to find out which peripheral a pad is actually muxed to when debugging.
+    fn read_config(&self) -> PinConfig;
 }
 
 /// Represents a pin peripheral created at run-time from given port and pin numbers.
@@ -404,6 +435,54 @@ macro_rules! impl_iopctlpin {
                 self.reg.reset();
                 self
             }
+
+            fn read_config(&self) -> PinConfig {
+                let r = self.reg.read();
+
+                PinConfig {
+                    function: match r.fsel().bits() {
+                        0 => Some(Function::F0),
+                        1 => Some(Function::F1),
+                        2 => Some(Function::F2),
+                        3 => Some(Function::F3),
+                        4 => Some(Function::F4),
+                        5 => Some(Function::F5),
+                        6 => Some(Function::F6),
+                        7 => Some(Function::F7),
+                        8 => Some(Function::F8),
+                        _ => None,
+                    },
+                    pull: if r.pupdena().is_disabled() {
+                        Pull::None
+                    } else if r.pupdsel().is_pull_up() {
+                        Pull::Up
+                    } else {
+                        Pull::Down
+                    },
+                    input_buffer: r.ibena().is_enabled(),
+                    slew_rate: if r.slewrate().is_slow() {
+                        SlewRate::Slow
+                    } else {
+                        SlewRate::Standard
+                    },
+                    drive_strength: if r.fulldrive().is_full_drive() {
+                        DriveStrength::Full
+                    } else {
+                        DriveStrength::Normal
+                    },
+                    analog_multiplex: r.amena().is_enabled(),
+                    drive_mode: if r.odena().is_enabled() {
+                        DriveMode::OpenDrain
+                    } else {
+                        DriveMode::PushPull
+                    },
+                    inverter: if r.iiena().is_enabled() {
+                        Inverter::Enabled
+                    } else {
+                        Inverter::Disabled
+                    },
+                }
+            }
         }
     };
 }
@@ -481,6 +560,11 @@ macro_rules! impl_FC15pin {
                 Self::to_raw($pin_no).reset();
                 self
             }
+
+            #[inline]
+            fn read_config(&self) -> PinConfig {
+                Self::to_raw($pin_no).read_config()
+            }
         }
     };
 }
@@ -555,6 +639,11 @@ macro_rules! impl_pin {
                 Self::to_raw($pin_port, $pin_no).reset();
                 self
             }
+
+            #[inline]
+            fn read_config(&self) -> PinConfig {
+                Self::to_raw($pin_port, $pin_no).read_config()
+            }
         }
     };
 }